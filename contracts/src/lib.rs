@@ -1,7 +1,7 @@
 #![no_std]
 use soroban_sdk::{
     contract, contractimpl, contracttype, symbol_short, Address, Env, Map, String, Symbol,
-    Vec, contracterror,
+    Vec, contracterror, xdr::{FromXdr, ToXdr}, Bytes, BytesN, IntoVal, TryFromVal, Val,
 };
 
 // Contract data types
@@ -13,13 +13,202 @@ pub struct DocumentRecord {
     pub registered_by: Address,
     pub timestamp: u64,
     pub block_number: u32,
+    pub encrypted: bool,
+    pub key_ref: Option<String>,
+    pub external_ref: Option<String>,
+    pub name_hash: BytesN<32>,
+    pub revocable_after: Option<u64>,
+    /// Imported by the admin with its original timestamp and block number
+    pub imported: bool,
+    /// Position in the global registration order, starting at 1, returned by
+    /// registration. Positions are never reused, so it stays unique after
+    /// deletions. 0 for temporary registrations, which are not ordered.
+    pub seq: u64,
+    /// Issuer's ed25519 signature over the UTF-8 bytes of `document_hash`
+    pub seal: Option<BytesN<64>>,
+    /// Issuer public key the seal was made with
+    pub seal_key: Option<BytesN<32>>,
+    /// Published as a template that other documents can be registered from
+    pub is_template: bool,
+    /// Hash of the template this document was registered from
+    pub template: Option<String>,
 }
 
+/// Optional settings supplied at registration
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RegistrationOptions {
+    /// Whether the off-chain document is encrypted
+    pub encrypted: bool,
+    /// Reference to the decryption key (e.g. a key ID), never the key itself
+    pub key_ref: Option<String>,
+    /// Caller-assigned reference ID, unique across the registry
+    pub external_ref: Option<String>,
+    /// Timestamp before which the document cannot be deleted
+    pub revocable_after: Option<u64>,
+}
+
+/// Optional records are carried as vectors of at most one entry:
+/// `soroban-sdk` testutils cannot convert `Option` fields holding contract
+/// types, so those would break the test build.
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct DocumentInfo {
     pub exists: bool,
-    pub record: Option<DocumentRecord>,
+    /// The record when the document exists, otherwise empty
+    pub record: Vec<DocumentRecord>,
+    pub status: DocumentStatus,
+    /// Set when the document was deleted
+    pub tombstone: Vec<Tombstone>,
+}
+
+/// Minimal trace left behind when a document is deleted
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Tombstone {
+    pub document_hash: String,
+    pub deleted_at: u64,
+    pub deleted_by: Address,
+}
+
+/// Current state of a document, derived from its record and the ledger time
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DocumentStatus {
+    NotFound,
+    Active,
+    /// Registered with a `revocable_after` time that has not been reached
+    Locked,
+    /// Deleted, with a tombstone left in place of the record
+    Deleted,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ContractStats {
+    pub total_documents: u64,
+    pub total_users: u32,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RebuildProgress {
+    pub processed: u32,
+    pub total: u32,
+    pub done: bool,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StateRootProgress {
+    pub accumulator: BytesN<32>,
+    pub next: u32,
+    pub done: bool,
+}
+
+/// Contract notified of every registration through its `on_register(document_hash, owner)` function
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RegistrationHook {
+    pub contract: Address,
+    /// Keep the registration when the hook call fails instead of aborting it
+    pub fail_open: bool,
+}
+
+/// A point-in-time export of a user's documents, as built by `export_user_snapshot`
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UserSnapshot {
+    pub owner: Address,
+    pub documents: Vec<DocumentRecord>,
+    pub ledger_sequence: u32,
+    pub timestamp: u64,
+    /// SHA-256 of the XDR-serialized `documents` vector
+    pub digest: BytesN<32>,
+}
+
+/// Differences between two document records, as reported by `compare_documents`
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DocumentDiff {
+    pub same_name: bool,
+    pub same_owner: bool,
+    /// Seconds from the first document's registration to the second's
+    pub time_delta: i64,
+}
+
+/// Settings applied in one step by `initialize_with_config`. Each limit must
+/// lie in the range its setter accepts. The history bound, links per document
+/// and name length are fixed by the contract and cannot be configured.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ContractConfig {
+    pub strict_names: bool,
+    pub global_name_unique: bool,
+    pub event_mode: EventMode,
+    /// Largest batch accepted, from 1 to `MAX_BATCH_SIZE_LIMIT`
+    pub max_batch_size: u32,
+    pub private_user_lists: bool,
+    /// Seconds between two registrations by the same owner, 0 for no limit,
+    /// at most `MAX_REGISTRATION_INTERVAL`
+    pub min_registration_interval: u64,
+    /// Ledgers between two registrations by the same owner, 0 for no limit,
+    /// at most `MAX_REGISTRATION_COOLDOWN`
+    pub registration_cooldown: u32,
+}
+
+/// Conditions for `query_documents`. Every field that is set, and a non-empty
+/// `statuses` list, must match.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DocumentFilter {
+    pub owner: Option<Address>,
+    pub from_ts: Option<u64>,
+    pub to_ts: Option<u64>,
+    /// Statuses to accept; empty accepts any
+    pub statuses: Vec<DocumentStatus>,
+}
+
+/// Comment left on a document by a verifier
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VerifierNote {
+    pub author: Address,
+    pub note: String,
+    pub timestamp: u64,
+}
+
+/// Challenge issued to a document owner for signing off-chain and answering
+/// with `verify_proof`
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ProofChallenge {
+    pub document_hash: String,
+    pub owner: Address,
+    pub nonce: u64,
+    pub expires_at: u64,
+}
+
+/// Kind of change recorded in a document's history
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum HistoryAction {
+    Registered,
+    Deleted,
+    Linked,
+    Rekeyed,
+    Transferred,
+    Unlinked,
+    Sealed,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HistoryEntry {
+    pub action: HistoryAction,
+    pub actor: Address,
+    pub timestamp: u64,
+    pub block_number: u32,
 }
 
 // Contract events
@@ -32,6 +221,80 @@ pub struct DocumentRegisteredEvent {
     pub timestamp: u64,
 }
 
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DocumentDeletedEvent {
+    pub document_hash: String,
+    pub deleted_by: Address,
+    pub timestamp: u64,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DocumentLinkedEvent {
+    pub from_hash: String,
+    pub to_hash: String,
+    pub relation: Symbol,
+    pub linked_by: Address,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AdminTransferredEvent {
+    pub previous_admin: Address,
+    pub new_admin: Address,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DocumentRekeyedEvent {
+    pub old_hash: String,
+    pub new_hash: String,
+    pub rekeyed_by: Address,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DocumentTransferredEvent {
+    pub document_hash: String,
+    pub from: Address,
+    pub to: Address,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DocumentsRelatedEvent {
+    pub hash_a: String,
+    pub hash_b: String,
+    pub relation: String,
+    pub linked_by: Address,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DocumentsUnlinkedEvent {
+    pub document_hash: String,
+    pub linked_hash: String,
+    pub relation: String,
+    pub unlinked_by: Address,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DocumentTaggedEvent {
+    pub document_hash: String,
+    pub tag: String,
+    pub added: bool,
+    pub tagged_by: Address,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct IndexesRebuiltEvent {
+    pub total: u32,
+    pub rebuilt_by: Address,
+}
+
 // Contract errors
 #[contracterror]
 #[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
@@ -40,185 +303,3741 @@ pub enum ContractError {
     InvalidHashLength = 1,
     InvalidDocumentName = 2,
     DocumentAlreadyExists = 3,
+    AlreadyInitialized = 4,
+    NotInitialized = 5,
+    Unauthorized = 6,
+    DocumentNameTaken = 7,
+    DocumentNotFound = 8,
+    NotDocumentOwner = 9,
+    InvalidKeyRef = 10,
+    NoPendingAdmin = 11,
+    InvalidExternalRef = 12,
+    ExternalRefTaken = 13,
+    BatchTooLarge = 14,
+    NameMismatch = 15,
+    DelegateLimitReached = 16,
+    NotADelegate = 17,
+    TooManyLinks = 18,
+    InvalidRelation = 19,
+    InvalidLockTime = 20,
+    DocumentLocked = 21,
+    InvalidCursor = 22,
+    InvalidConfig = 23,
+    TooManyTags = 24,
+    InvalidTag = 25,
+    InvalidTimeRange = 26,
+    RateLimited = 27,
+    InvalidNonce = 28,
+    CooldownActive = 29,
+    InvalidImportTime = 30,
+    InvalidTtl = 31,
+    OperationPaused = 32,
+    TooManyNotes = 33,
+    InvalidNote = 34,
+    NotATemplate = 35,
+    HashBlocklisted = 36,
+    RequiredDocumentMissing = 37,
+    HashTombstoned = 38,
+    DocumentSealed = 39,
+    RegistryNotPaused = 40,
+    ProofChallengeInvalid = 41,
+    ProofChallengeExpired = 42,
+}
+
+/// Kind of state-changing operation that can be paused on its own
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum OperationKind {
+    Register,
+    Delete,
+    Transfer,
+    Rekey,
+    Link,
+    Tag,
+    Seal,
+}
+
+/// Which events the contract publishes
+///
+/// - `Full`: every event.
+/// - `Minimal`: only critical events, i.e. registration, deletion, transfer,
+///   rekey and admin transfer.
+/// - `Off`: no events at all.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EventMode {
+    Full,
+    Minimal,
+    Off,
+}
+
+/// Whether an event is published in `Minimal` mode
+#[derive(Clone, Copy, PartialEq)]
+enum EventPriority {
+    Critical,
+    Detail,
 }
 
 // Storage keys
 const DOCUMENTS: Symbol = symbol_short!("DOCS");
 const DOC_COUNT: Symbol = symbol_short!("COUNT");
 const USER_DOCS: Symbol = symbol_short!("USERDOCS");
+const ADMIN: Symbol = symbol_short!("ADMIN");
+const PENDING_ADMIN: Symbol = symbol_short!("PENDADM");
+const STRICT_NAMES: Symbol = symbol_short!("STRICT");
+const RELATIONS: Symbol = symbol_short!("RELATED");
+const RELATION_SOURCES: Symbol = symbol_short!("RELFROM");
+const LINKS: Symbol = symbol_short!("LINKS");
+const LINK_AUTHORS: Symbol = symbol_short!("LINKAUTH");
+const TAGS: Symbol = symbol_short!("TAGS");
+const TAG_INDEX: Symbol = symbol_short!("TAGINDEX");
+const NOTES: Symbol = symbol_short!("NOTES");
+const TEMPLATE_INSTANCES: Symbol = symbol_short!("TMPLINST");
+const DEPENDENCIES: Symbol = symbol_short!("DEPENDS");
+const HISTORY: Symbol = symbol_short!("HISTORY");
+const REGISTRANT: Symbol = symbol_short!("REGSTRNT");
+const UNIQUE_USERS: Symbol = symbol_short!("UNIQUE");
+const REGISTRANTS: Symbol = symbol_short!("REGLIST");
+const DELEGATES: Symbol = symbol_short!("DELEGATE");
+const REBUILD_CURSOR: Symbol = symbol_short!("REBUILD");
+const REBUILD_NAME_COUNTS: Symbol = symbol_short!("RBNAMES");
+const REBUILD_REF_INDEX: Symbol = symbol_short!("RBREFS");
+const EVENT_MODE: Symbol = symbol_short!("EVTMODE");
+const PROOF_CHALLENGES: Symbol = symbol_short!("CHALLNGE");
+const CHALLENGE_NONCE: Symbol = symbol_short!("CHALNONC");
+const BLOCK_REGISTRATIONS: Symbol = symbol_short!("BLOCKREG");
+const PRIVATE_USER_LISTS: Symbol = symbol_short!("PRIVLIST");
+const MIN_REGISTRATION_INTERVAL: Symbol = symbol_short!("MININTVL");
+const LAST_REGISTRATION: Symbol = symbol_short!("LASTREG");
+const REGISTRATION_COOLDOWN: Symbol = symbol_short!("COOLDOWN");
+const LAST_REGISTRATION_LEDGER: Symbol = symbol_short!("LASTLDGR");
+const ORDER_POSITIONS: Symbol = symbol_short!("ORDERPOS");
+const ORDER_HASHES: Symbol = symbol_short!("ORDERIDX");
+const NEXT_ORDER: Symbol = symbol_short!("ORDERNXT");
+const JOURNAL: Symbol = symbol_short!("JOURNAL");
+const JOURNAL_LENGTH: Symbol = symbol_short!("JOURNLEN");
+const SIGNATURE_NONCES: Symbol = symbol_short!("SIGNONCE");
+const REGISTRATION_HOOK: Symbol = symbol_short!("REGHOOK");
+const TEMPORARY_DOCUMENTS: Symbol = symbol_short!("TEMPDOC");
+const BLOCKLIST: Symbol = symbol_short!("BLOCKLST");
+const FIRST_TIMESTAMP: Symbol = symbol_short!("FIRSTTS");
+const LAST_TIMESTAMP: Symbol = symbol_short!("LASTTS");
+const PAUSED_OPERATIONS: Symbol = symbol_short!("PAUSEDOP");
+const TOMBSTONES: Symbol = symbol_short!("TOMBSTN");
+const MAX_BATCH_SIZE: Symbol = symbol_short!("BATCHMAX");
+const REF_INDEX: Symbol = symbol_short!("REFINDEX");
+const NAME_COUNTS: Symbol = symbol_short!("NAMECNT");
+const GLOBAL_NAME_UNIQUE: Symbol = symbol_short!("GLOBNAME");
 
-#[contract]
-pub struct DocumentVerificationContract;
+// Validation limits
+const HASH_LENGTH: u32 = 64;
+const MAX_NAME_LENGTH: u32 = 64;
+const MAX_KEY_REF_LENGTH: u32 = 128;
+const MAX_EXTERNAL_REF_LENGTH: u32 = 64;
+const MAX_HISTORY_ENTRIES: u32 = 50;
+const DEFAULT_MAX_BATCH_SIZE: u32 = 50;
+const MAX_BATCH_SIZE_LIMIT: u32 = 200;
+const MAX_REGISTRATION_INTERVAL: u64 = 30 * 24 * 60 * 60; // 30 days
+const MAX_REGISTRATION_COOLDOWN: u32 = 518_400; // about 30 days of ledgers
+const MAX_STRING_BYTES: usize = 256;
+const MAX_PAGE_SIZE: u32 = 100;
+const MAX_LINKS: u32 = 20;
+const MAX_RELATION_LENGTH: u32 = 32;
+const MAX_TAGS: u32 = 20;
+const MAX_TAG_LENGTH: u32 = 32;
+const PROOF_CHALLENGE_TTL: u64 = 300;
+const MAX_NOTES: u32 = 50;
+const MAX_NOTE_LENGTH: u32 = 256;
 
-#[contractimpl]
-impl DocumentVerificationContract {
-    /// Initialize the contract
-    pub fn initialize(env: Env) {
-        // Set initial document count to 0
-        env.storage().instance().set(&DOC_COUNT, &0u64);
+/// Publish an event unless the configured event mode suppresses it
+fn publish_event<D: IntoVal<Env, Val>>(env: &Env, priority: EventPriority, topic: Symbol, data: D) {
+    let mode: EventMode = env
+        .storage()
+        .instance()
+        .get(&EVENT_MODE)
+        .unwrap_or(EventMode::Full);
+    let publish = match mode {
+        EventMode::Full => true,
+        EventMode::Minimal => priority == EventPriority::Critical,
+        EventMode::Off => false,
+    };
+    if publish {
+        env.events().publish((topic,), data);
     }
+}
 
-    /// Register a new document
-    pub fn register_document(
-        env: Env,
-        caller: Address,
-        document_hash: String,
-        document_name: String,
-    ) -> Result<u64, ContractError> {
-        // Require caller authorization
-        caller.require_auth();
+/// Reject batches larger than the configured maximum batch size
+fn check_batch_size(env: &Env, len: u32) -> Result<(), ContractError> {
+    if len > DocumentVerificationContract::get_max_batch_size(env.clone()) {
+        return Err(ContractError::BatchTooLarge);
+    }
+    Ok(())
+}
 
-        // Validate inputs
-        if document_hash.len() != 64 {
-            return Err(ContractError::InvalidHashLength);
-        }
+/// Check the limits in a `ContractConfig` against the ranges their setters accept
+fn check_config(config: &ContractConfig) -> Result<(), ContractError> {
+    if config.max_batch_size == 0
+        || config.max_batch_size > MAX_BATCH_SIZE_LIMIT
+        || config.min_registration_interval > MAX_REGISTRATION_INTERVAL
+        || config.registration_cooldown > MAX_REGISTRATION_COOLDOWN
+    {
+        return Err(ContractError::InvalidConfig);
+    }
+    Ok(())
+}
 
-        if document_name.len() == 0 || document_name.len() > 64 {
-            return Err(ContractError::InvalidDocumentName);
-        }
+/// Load the map of all registered documents
+fn load_documents(env: &Env) -> Map<String, DocumentRecord> {
+    env.storage()
+        .persistent()
+        .get(&DOCUMENTS)
+        .unwrap_or(Map::new(env))
+}
 
-        // Check if document already exists
-        let documents: Map<String, DocumentRecord> = env
-            .storage()
-            .persistent()
-            .get(&DOCUMENTS)
-            .unwrap_or(Map::new(&env));
+/// Move a per-document persistent entry from one hash to another
+fn move_document_data<V>(env: &Env, prefix: Symbol, from: &String, to: &String)
+where
+    V: IntoVal<Env, Val> + TryFromVal<Env, Val>,
+{
+    let from_key = (prefix.clone(), from.clone());
+    let value: Option<V> = env.storage().persistent().get(&from_key);
+    if let Some(value) = value {
+        env.storage().persistent().set(&(prefix, to.clone()), &value);
+        env.storage().persistent().remove(&from_key);
+    }
+}
 
-        if documents.contains_key(document_hash.clone()) {
-            return Err(ContractError::DocumentAlreadyExists);
-        }
+/// Load the bidirectional links of a document as (linked hash, relation) pairs
+fn load_links(env: &Env, document_hash: &String) -> Vec<(String, String)> {
+    env.storage()
+        .persistent()
+        .get(&(LINKS, document_hash.clone()))
+        .unwrap_or(Vec::new(env))
+}
 
-        // Get current timestamp and block number
-        let timestamp = env.ledger().timestamp();
-        let block_number = env.ledger().sequence();
+/// Load who added each link held by a document, keyed by (linked hash, relation)
+fn load_link_authors(env: &Env, document_hash: &String) -> Map<(String, String), Address> {
+    env.storage()
+        .persistent()
+        .get(&(LINK_AUTHORS, document_hash.clone()))
+        .unwrap_or(Map::new(env))
+}
 
-        // Create document record
-        let record = DocumentRecord {
-            document_hash: document_hash.clone(),
-            document_name: document_name.clone(),
-            registered_by: caller.clone(),
-            timestamp,
-            block_number,
-        };
+/// Fail with `TooManyLinks` if `document_hash` cannot take another link from
+/// `author`. Links added by the document's owner and links added by anyone
+/// else are capped separately, so outsiders cannot use up the owner's share.
+/// Links without a recorded author count as the owner's.
+fn check_link_capacity(
+    env: &Env,
+    document_hash: &String,
+    owner: &Address,
+    author: &Address,
+) -> Result<(), ContractError> {
+    let links = load_links(env, document_hash);
+    let authors = load_link_authors(env, document_hash);
+    let mut owner_links = 0;
+    for entry in links.iter() {
+        if authors.get(entry).is_none_or(|added_by| added_by == *owner) {
+            owner_links += 1;
+        }
+    }
+    let used = if author == owner {
+        owner_links
+    } else {
+        links.len() - owner_links
+    };
+    if used >= MAX_LINKS {
+        return Err(ContractError::TooManyLinks);
+    }
+    Ok(())
+}
 
-        // Store document
-        let mut updated_documents = documents;
-        updated_documents.set(document_hash.clone(), record.clone());
+/// Drop one link entry, and its author, from a document's links
+fn remove_link(env: &Env, document_hash: &String, linked_hash: &String, relation: &String) {
+    let entry = (linked_hash.clone(), relation.clone());
+    let mut links = load_links(env, document_hash);
+    if let Some(index) = links.first_index_of(entry.clone()) {
+        links.remove(index);
         env.storage()
             .persistent()
-            .set(&DOCUMENTS, &updated_documents);
-
-        // Update user's document list
-        let user_docs_key = (USER_DOCS, caller.clone());
-        let mut user_docs: Vec<String> = env
-            .storage()
+            .set(&(LINKS, document_hash.clone()), &links);
+    }
+    let mut authors = load_link_authors(env, document_hash);
+    if authors.contains_key(entry.clone()) {
+        authors.remove(entry);
+        env.storage()
             .persistent()
-            .get(&user_docs_key)
-            .unwrap_or(Vec::new(&env));
-        user_docs.push_back(document_hash.clone());
-        env.storage().persistent().set(&user_docs_key, &user_docs);
-
-        // Increment document count
-        let count: u64 = env
-            .storage()
-            .instance()
-            .get(&DOC_COUNT)
-            .unwrap_or(0);
-        env.storage().instance().set(&DOC_COUNT, &(count + 1));
+            .set(&(LINK_AUTHORS, document_hash.clone()), &authors);
+    }
+}
 
-        // Emit event
-        env.events().publish(
-            (symbol_short!("DOC_REG"),),
-            DocumentRegisteredEvent {
-                document_hash: document_hash.clone(),
-                document_name,
-                registered_by: caller,
-                timestamp,
-            },
-        );
+/// Point the reverse side of every link held by `document_hash` at `new_hash`,
+/// or drop those reverse entries when `new_hash` is `None`
+fn update_reverse_links(env: &Env, document_hash: &String, new_hash: Option<&String>) {
+    for (linked_hash, relation) in load_links(env, document_hash).iter() {
+        let reverse = load_links(env, &linked_hash);
+        let mut updated = Vec::new(env);
+        for (other_hash, other_relation) in reverse.iter() {
+            if other_hash != *document_hash || other_relation != relation {
+                updated.push_back((other_hash, other_relation));
+            } else if let Some(new_hash) = new_hash {
+                updated.push_back((new_hash.clone(), other_relation));
+            }
+        }
+        env.storage()
+            .persistent()
+            .set(&(LINKS, linked_hash.clone()), &updated);
 
-        Ok(count + 1)
+        let mut authors = load_link_authors(env, &linked_hash);
+        let old_entry = (document_hash.clone(), relation.clone());
+        if let Some(author) = authors.get(old_entry.clone()) {
+            authors.remove(old_entry);
+            if let Some(new_hash) = new_hash {
+                authors.set((new_hash.clone(), relation), author);
+            }
+            env.storage()
+                .persistent()
+                .set(&(LINK_AUTHORS, linked_hash), &authors);
+        }
     }
+}
 
-    /// Verify if a document exists
-    pub fn verify_document(env: Env, document_hash: String) -> DocumentInfo {
-        let documents: Map<String, DocumentRecord> = env
-            .storage()
-            .persistent()
-            .get(&DOCUMENTS)
-            .unwrap_or(Map::new(&env));
+/// Load a document's typed relations to other documents
+fn load_relations(env: &Env, document_hash: &String) -> Vec<(Symbol, String)> {
+    env.storage()
+        .persistent()
+        .get(&(RELATIONS, document_hash.clone()))
+        .unwrap_or(Vec::new(env))
+}
 
-        match documents.get(document_hash) {
-            Some(record) => DocumentInfo {
-                exists: true,
-                record: Some(record),
-            },
-            None => DocumentInfo {
-                exists: false,
-                record: None,
-            },
+/// Load the hashes of the documents holding a relation towards `document_hash`
+fn load_relation_sources(env: &Env, document_hash: &String) -> Vec<String> {
+    env.storage()
+        .persistent()
+        .get(&(RELATION_SOURCES, document_hash.clone()))
+        .unwrap_or(Vec::new(env))
+}
+
+/// Point the other documents' side of every relation held by or towards
+/// `document_hash` at `new_hash`, or drop it when `new_hash` is `None`. The
+/// document's own relations and sources are left for the caller to move.
+fn update_reverse_relations(env: &Env, document_hash: &String, new_hash: Option<&String>) {
+    for (_, target_hash) in load_relations(env, document_hash).iter() {
+        let mut sources = load_relation_sources(env, &target_hash);
+        if let Some(index) = sources.first_index_of(document_hash.clone()) {
+            match new_hash {
+                Some(new_hash) => sources.set(index, new_hash.clone()),
+                None => {
+                    sources.remove(index);
+                }
+            }
+            env.storage()
+                .persistent()
+                .set(&(RELATION_SOURCES, target_hash), &sources);
         }
     }
 
-    /// Get all documents registered by a user
-    pub fn get_user_documents(env: Env, user: Address) -> Vec<DocumentRecord> {
-        let user_docs_key = (USER_DOCS, user);
-        let user_doc_hashes: Vec<String> = env
-            .storage()
+    for source_hash in load_relation_sources(env, document_hash).iter() {
+        let mut updated = Vec::new(env);
+        for (relation, target_hash) in load_relations(env, &source_hash).iter() {
+            if target_hash != *document_hash {
+                updated.push_back((relation, target_hash));
+            } else if let Some(new_hash) = new_hash {
+                updated.push_back((relation, new_hash.clone()));
+            }
+        }
+        env.storage()
             .persistent()
-            .get(&user_docs_key)
-            .unwrap_or(Vec::new(&env));
+            .set(&(RELATIONS, source_hash), &updated);
+    }
+}
 
-        let documents: Map<String, DocumentRecord> = env
-            .storage()
-            .persistent()
-            .get(&DOCUMENTS)
-            .unwrap_or(Map::new(&env));
+/// Load the tags attached to a document
+fn load_tags(env: &Env, document_hash: &String) -> Vec<String> {
+    env.storage()
+        .persistent()
+        .get(&(TAGS, document_hash.clone()))
+        .unwrap_or(Vec::new(env))
+}
 
-        let mut result = Vec::new(&env);
-        for hash in user_doc_hashes.iter() {
-            if let Some(record) = documents.get(hash) {
-                result.push_back(record);
+/// Load the map from each tag to the hashes of the documents carrying it
+fn load_tag_index(env: &Env) -> Map<String, Vec<String>> {
+    env.storage()
+        .persistent()
+        .get(&TAG_INDEX)
+        .unwrap_or(Map::new(env))
+}
+
+/// Point the tag index entries of `document_hash` at `new_hash`, or drop them
+/// when `new_hash` is `None`
+fn update_tag_index(env: &Env, document_hash: &String, new_hash: Option<&String>) {
+    let tags = load_tags(env, document_hash);
+    if tags.is_empty() {
+        return;
+    }
+    let mut tag_index = load_tag_index(env);
+    for tag in tags.iter() {
+        let mut hashes = tag_index.get(tag.clone()).unwrap_or(Vec::new(env));
+        if let Some(index) = hashes.first_index_of(document_hash.clone()) {
+            match new_hash {
+                Some(new_hash) => hashes.set(index, new_hash.clone()),
+                None => {
+                    hashes.remove(index);
+                }
             }
         }
+        if hashes.is_empty() {
+            tag_index.remove(tag);
+        } else {
+            tag_index.set(tag, hashes);
+        }
+    }
+    env.storage().persistent().set(&TAG_INDEX, &tag_index);
+}
 
-        result
+/// Append an entry to a document's history. Once the history holds
+/// `MAX_HISTORY_ENTRIES` entries the oldest one is dropped.
+fn append_history(env: &Env, document_hash: &String, action: HistoryAction, actor: &Address) {
+    let history_key = (HISTORY, document_hash.clone());
+    let mut history: Vec<HistoryEntry> = env
+        .storage()
+        .persistent()
+        .get(&history_key)
+        .unwrap_or(Vec::new(env));
+    if history.len() >= MAX_HISTORY_ENTRIES {
+        history.pop_front();
     }
+    history.push_back(HistoryEntry {
+        action,
+        actor: actor.clone(),
+        timestamp: env.ledger().timestamp(),
+        block_number: env.ledger().sequence(),
+    });
+    env.storage().persistent().set(&history_key, &history);
+}
 
-    /// Get total number of registered documents
-    pub fn get_document_count(env: Env) -> u64 {
-        env.storage().instance().get(&DOC_COUNT).unwrap_or(0)
+/// Load the map from external reference IDs to document hashes
+fn load_ref_index(env: &Env) -> Map<String, String> {
+    env.storage()
+        .persistent()
+        .get(&REF_INDEX)
+        .unwrap_or(Map::new(env))
+}
+
+/// Load the document count, deriving it from the stored records if the
+/// counter is missing (for example after its instance entry was archived)
+fn load_document_count(env: &Env) -> u64 {
+    env.storage()
+        .instance()
+        .get(&DOC_COUNT)
+        .unwrap_or_else(|| load_documents(env).len() as u64)
+}
+
+/// Derive the account address of an ed25519 public key by decoding the XDR of
+/// an `ScVal::Address` holding that key as an account ID
+fn account_address(env: &Env, public_key: &BytesN<32>) -> Address {
+    let mut address_xdr = Bytes::from_array(env, &[0, 0, 0, 18, 0, 0, 0, 0, 0, 0, 0, 0]);
+    address_xdr.append(&Bytes::from(public_key.clone()));
+    Address::from_xdr(env, &address_xdr).unwrap()
+}
+
+/// Notify the registration hook, if one is set, of a new document
+fn call_registration_hook(env: &Env, document_hash: &String, owner: &Address) {
+    let Some(hook) = DocumentVerificationContract::get_registration_hook(env.clone()) else {
+        return;
+    };
+    let function = Symbol::new(env, "on_register");
+    let args: Vec<Val> = (document_hash.clone(), owner.clone()).into_val(env);
+    if hook.fail_open {
+        let _ = env.try_invoke_contract::<Val, soroban_sdk::Error>(&hook.contract, &function, args);
+    } else {
+        env.invoke_contract::<Val>(&hook.contract, &function, args);
     }
+}
 
-    /// Check if a document name is already used by a user
-    pub fn is_document_name_used(env: Env, user: Address, document_name: String) -> bool {
-        let user_docs = Self::get_user_documents(env, user);
+/// Whether the admin has blocklisted a hash
+fn is_hash_blocklisted(env: &Env, document_hash: &String) -> bool {
+    env.storage()
+        .persistent()
+        .has(&(BLOCKLIST, document_hash.clone()))
+}
 
-        for doc in user_docs.iter() {
-            if doc.document_name == document_name {
-                return true;
-            }
-        }
+/// Whether a deleted document left a tombstone under a hash
+fn is_hash_tombstoned(env: &Env, document_hash: &String) -> bool {
+    env.storage()
+        .persistent()
+        .has(&(TOMBSTONES, document_hash.clone()))
+}
 
-        false
+/// Look up a live document among the permanent records, then among the
+/// temporary ones
+fn find_document(
+    env: &Env,
+    documents: &Map<String, DocumentRecord>,
+    document_hash: &String,
+) -> Option<DocumentRecord> {
+    documents.get(document_hash.clone()).or_else(|| {
+        env.storage()
+            .temporary()
+            .get(&(TEMPORARY_DOCUMENTS, document_hash.clone()))
+    })
+}
+
+/// Fail with `OperationPaused` if the admin has paused `operation`
+fn check_not_paused(env: &Env, operation: OperationKind) -> Result<(), ContractError> {
+    if DocumentVerificationContract::is_operation_paused(env.clone(), operation) {
+        return Err(ContractError::OperationPaused);
     }
+    Ok(())
+}
 
-    /// Get document by name for a specific user
-    pub fn get_document_by_name(env: Env, user: Address, document_name: String) -> DocumentInfo {
-        let user_docs = Self::get_user_documents(env, user);
+/// Load the list of document hashes owned by a user. The list is kept in
+/// `seq` order.
+fn load_user_docs(env: &Env, user: &Address) -> Vec<String> {
+    env.storage()
+        .persistent()
+        .get(&(USER_DOCS, user.clone()))
+        .unwrap_or(Vec::new(env))
+}
 
-        for doc in user_docs.iter() {
-            if doc.document_name == document_name {
-                return DocumentInfo {
-                    exists: true,
-                    record: Some(doc),
-                };
-            }
+/// Find the first position in a user's list whose document has a `seq` of at
+/// least `seq`. Hashes without a record are treated as sequence 0.
+fn seq_position(documents: &Map<String, DocumentRecord>, user_docs: &Vec<String>, seq: u64) -> u32 {
+    let (mut low, mut high) = (0, user_docs.len());
+    while low < high {
+        let mid = low + (high - low) / 2;
+        let mid_seq = user_docs
+            .get(mid)
+            .and_then(|document_hash| documents.get(document_hash))
+            .map_or(0, |record| record.seq);
+        if mid_seq < seq {
+            low = mid + 1;
+        } else {
+            high = mid;
         }
+    }
+    low
+}
 
-        DocumentInfo {
-            exists: false,
-            record: None,
+/// Move the listed documents from `from` to `to`, skipping any not owned by
+/// `from`, and slot each into `to`'s list by `seq`. Callers are expected to
+/// have validated ownership already.
+fn transfer_documents(
+    env: &Env,
+    documents: &mut Map<String, DocumentRecord>,
+    from: &Address,
+    to: &Address,
+    hashes: &Vec<String>,
+) {
+    if from == to {
+        return;
+    }
+
+    let mut from_docs = load_user_docs(env, from);
+    let mut to_docs = load_user_docs(env, to);
+
+    for document_hash in hashes.iter() {
+        let mut record = match documents.get(document_hash.clone()) {
+            Some(record) if record.registered_by == *from => record,
+            _ => continue,
+        };
+        record.registered_by = to.clone();
+        let seq = record.seq;
+        documents.set(document_hash.clone(), record);
+
+        if let Some(index) = from_docs.first_index_of(document_hash.clone()) {
+            from_docs.remove(index);
         }
+        to_docs.insert(seq_position(documents, &to_docs, seq), document_hash.clone());
+
+        append_history(env, &document_hash, HistoryAction::Transferred, from);
+        env.storage()
+            .persistent()
+            .remove(&(PROOF_CHALLENGES, from.clone(), document_hash.clone()));
+
+        publish_event(
+            env,
+            EventPriority::Critical,
+            symbol_short!("DOC_XFER"),
+            DocumentTransferredEvent {
+                document_hash,
+                from: from.clone(),
+                to: to.clone(),
+            },
+        );
     }
+
+    env.storage().persistent().set(&DOCUMENTS, documents);
+    env.storage()
+        .persistent()
+        .set(&(USER_DOCS, from.clone()), &from_docs);
+    env.storage()
+        .persistent()
+        .set(&(USER_DOCS, to.clone()), &to_docs);
+}
+
+/// Load the map counting how many documents use each exact name
+fn load_name_counts(env: &Env) -> Map<String, u32> {
+    env.storage()
+        .persistent()
+        .get(&NAME_COUNTS)
+        .unwrap_or(Map::new(env))
 }
+
+/// Drop one use of a name from the name counts
+fn release_name(name_counts: &mut Map<String, u32>, document_name: &String) {
+    match name_counts.get(document_name.clone()).unwrap_or(0) {
+        0 | 1 => {
+            name_counts.remove(document_name.clone());
+        }
+        uses => name_counts.set(document_name.clone(), uses - 1),
+    }
+}
+
+/// Throttle owners that register faster than the configured minimum
+/// interval or within their ledger cooldown
+fn check_registration_throttle(env: &Env, owner: &Address) -> Result<(), ContractError> {
+    let min_interval = DocumentVerificationContract::get_min_registration_interval(env.clone());
+    if min_interval > 0 {
+        let last_registration: Option<u64> = env
+            .storage()
+            .persistent()
+            .get(&(LAST_REGISTRATION, owner.clone()));
+        if let Some(last_registration) = last_registration
+            && env.ledger().timestamp() < last_registration.saturating_add(min_interval)
+        {
+            return Err(ContractError::RateLimited);
+        }
+    }
+    if DocumentVerificationContract::get_user_cooldown_remaining(env.clone(), owner.clone()) > 0 {
+        return Err(ContractError::CooldownActive);
+    }
+    Ok(())
+}
+
+/// Remember when an owner last registered, for the throttle checks
+fn record_registration_time(env: &Env, owner: &Address) {
+    env.storage()
+        .persistent()
+        .set(&(LAST_REGISTRATION, owner.clone()), &env.ledger().timestamp());
+    env.storage()
+        .persistent()
+        .set(&(LAST_REGISTRATION_LEDGER, owner.clone()), &env.ledger().sequence());
+}
+
+/// Registration options used when the caller supplies none
+fn default_options() -> RegistrationOptions {
+    RegistrationOptions {
+        encrypted: false,
+        key_ref: None,
+        external_ref: None,
+        revocable_after: None,
+    }
+}
+
+/// How a record being stored came about, beyond the caller's options
+#[derive(Default)]
+struct RecordOrigin {
+    /// Original (timestamp, block number) of a document imported by the
+    /// admin, which is exempt from registration throttling
+    imported_at: Option<(u64, u32)>,
+    is_template: bool,
+    /// Template the document is registered from, already validated
+    template: Option<String>,
+    /// Ledgers to keep a temporary registration for, already validated
+    temporary_ttl: Option<u32>,
+}
+
+/// Validate and store a new document owned by `owner`, returning its `seq`.
+/// Authorization is the caller's responsibility; `actor` is recorded in the
+/// document's history.
+fn store_document(
+    env: &Env,
+    owner: &Address,
+    actor: &Address,
+    document_hash: String,
+    document_name: String,
+    options: RegistrationOptions,
+    origin: RecordOrigin,
+) -> Result<u64, ContractError> {
+    let imported_at = origin.imported_at;
+    check_not_paused(env, OperationKind::Register)?;
+
+    // Validate inputs
+    let document_hash = canonical_hash(env, document_hash);
+    if document_hash.len() != HASH_LENGTH {
+        return Err(ContractError::InvalidHashLength);
+    }
+
+    // Documents registered without a name are named after their hash
+    let document_name = if document_name.is_empty() {
+        default_document_name(env, &document_hash)
+    } else {
+        document_name
+    };
+    if document_name.len() > MAX_NAME_LENGTH {
+        return Err(ContractError::InvalidDocumentName);
+    }
+
+    if let Some(key_ref) = &options.key_ref
+        && (key_ref.is_empty() || key_ref.len() > MAX_KEY_REF_LENGTH)
+    {
+        return Err(ContractError::InvalidKeyRef);
+    }
+
+    if let Some(external_ref) = &options.external_ref
+        && (external_ref.is_empty() || external_ref.len() > MAX_EXTERNAL_REF_LENGTH)
+    {
+        return Err(ContractError::InvalidExternalRef);
+    }
+
+    if let Some(revocable_after) = options.revocable_after
+        && revocable_after <= env.ledger().timestamp()
+    {
+        return Err(ContractError::InvalidLockTime);
+    }
+
+    if is_hash_blocklisted(env, &document_hash) {
+        return Err(ContractError::HashBlocklisted);
+    }
+    if is_hash_tombstoned(env, &document_hash) {
+        return Err(ContractError::HashTombstoned);
+    }
+
+    // Check if document already exists
+    let documents = load_documents(env);
+
+    if find_document(env, &documents, &document_hash).is_some() {
+        return Err(ContractError::DocumentAlreadyExists);
+    }
+
+    let user_docs_key = (USER_DOCS, owner.clone());
+    let mut user_docs: Vec<String> = env
+        .storage()
+        .persistent()
+        .get(&user_docs_key)
+        .unwrap_or(Vec::new(env));
+
+    // In strict mode, names that only differ in whitespace count as taken.
+    // The name is still stored exactly as given.
+    let strict_names: bool = env
+        .storage()
+        .instance()
+        .get(&STRICT_NAMES)
+        .unwrap_or(false);
+    if strict_names {
+        for hash in user_docs.iter() {
+            if let Some(existing) = documents.get(hash)
+                && names_collide(&existing.document_name, &document_name)
+            {
+                return Err(ContractError::DocumentNameTaken);
+            }
+        }
+    }
+
+    // With global uniqueness, a name may only be used once in the registry
+    let mut name_counts = load_name_counts(env);
+    let name_uses = name_counts.get(document_name.clone()).unwrap_or(0);
+    if name_uses > 0 && DocumentVerificationContract::is_global_name_unique(env.clone()) {
+        return Err(ContractError::DocumentNameTaken);
+    }
+    name_counts.set(document_name.clone(), name_uses + 1);
+
+    // External references must be unique across the registry
+    let mut ref_index = load_ref_index(env);
+    if let Some(external_ref) = &options.external_ref {
+        if ref_index.contains_key(external_ref.clone()) {
+            return Err(ContractError::ExternalRefTaken);
+        }
+        ref_index.set(external_ref.clone(), document_hash.clone());
+    }
+
+    // Get current timestamp and block number, or the original ones of an import
+    let (timestamp, block_number) =
+        imported_at.unwrap_or((env.ledger().timestamp(), env.ledger().sequence()));
+
+    if imported_at.is_none() {
+        check_registration_throttle(env, owner)?;
+    }
+
+    // Read the count before the new record is stored, in case it has to be
+    // derived from the stored records
+    let count = load_document_count(env);
+    let position: u32 = env.storage().instance().get(&NEXT_ORDER).unwrap_or(0);
+
+    // Create document record
+    let record = DocumentRecord {
+        document_hash: document_hash.clone(),
+        document_name: document_name.clone(),
+        registered_by: owner.clone(),
+        timestamp,
+        block_number,
+        encrypted: options.encrypted,
+        key_ref: options.key_ref,
+        external_ref: options.external_ref.clone(),
+        name_hash: hash_name(env, &document_name),
+        revocable_after: options.revocable_after,
+        imported: imported_at.is_some(),
+        seq: match origin.temporary_ttl {
+            Some(_) => 0,
+            None => position as u64 + 1,
+        },
+        seal: None,
+        seal_key: None,
+        is_template: origin.is_template,
+        template: origin.template.clone(),
+    };
+
+    // Temporary records expire without notice, so they are checked against
+    // the counts and indexes but never added to them
+    if let Some(ttl_ledgers) = origin.temporary_ttl {
+        let key = (TEMPORARY_DOCUMENTS, document_hash.clone());
+        env.storage().temporary().set(&key, &record);
+        env.storage().temporary().extend_ttl(&key, ttl_ledgers, ttl_ledgers);
+        record_registration_time(env, owner);
+
+        publish_event(
+            env,
+            EventPriority::Critical,
+            symbol_short!("DOC_REG"),
+            DocumentRegisteredEvent {
+                document_hash: document_hash.clone(),
+                document_name,
+                registered_by: owner.clone(),
+                timestamp,
+            },
+        );
+
+        call_registration_hook(env, &document_hash, owner);
+
+        return Ok(record.seq);
+    }
+
+    // Store document
+    let mut updated_documents = documents;
+    updated_documents.set(document_hash.clone(), record.clone());
+    env.storage()
+        .persistent()
+        .set(&DOCUMENTS, &updated_documents);
+    env.storage().persistent().set(&NAME_COUNTS, &name_counts);
+    if options.external_ref.is_some() {
+        env.storage().persistent().set(&REF_INDEX, &ref_index);
+    }
+    if imported_at.is_none() {
+        record_registration_time(env, owner);
+    }
+
+    // Count and index the owner the first time it ever registers a document
+    let registrant_key = (REGISTRANT, owner.clone());
+    if !env.storage().persistent().has(&registrant_key) {
+        env.storage().persistent().set(&registrant_key, &true);
+        let unique_users: u32 = env
+            .storage()
+            .instance()
+            .get(&UNIQUE_USERS)
+            .unwrap_or(0);
+        env.storage()
+            .persistent()
+            .set(&(REGISTRANTS, unique_users), owner);
+        env.storage()
+            .instance()
+            .set(&UNIQUE_USERS, &(unique_users + 1));
+    }
+
+    // Update user's document list
+    user_docs.push_back(document_hash.clone());
+    env.storage().persistent().set(&user_docs_key, &user_docs);
+
+    // Index the document under the template it was registered from
+    if let Some(template) = origin.template {
+        let instances_key = (TEMPLATE_INSTANCES, template);
+        let mut instances: Vec<String> = env
+            .storage()
+            .persistent()
+            .get(&instances_key)
+            .unwrap_or(Vec::new(env));
+        instances.push_back(document_hash.clone());
+        env.storage().persistent().set(&instances_key, &instances);
+    }
+
+    // Count registrations per ledger. Imports were not anchored in the
+    // ledger they claim, so they are left out.
+    if imported_at.is_none() {
+        let block_key = (BLOCK_REGISTRATIONS, block_number);
+        let block_registrations: u32 = env.storage().persistent().get(&block_key).unwrap_or(0);
+        env.storage()
+            .persistent()
+            .set(&block_key, &(block_registrations + 1));
+    }
+
+    // Increment document count
+    env.storage().instance().set(&DOC_COUNT, &(count + 1));
+
+    // Widen the registry span. Imports can predate earlier registrations.
+    let first_timestamp: Option<u64> = env.storage().instance().get(&FIRST_TIMESTAMP);
+    if first_timestamp.is_none_or(|first_timestamp| timestamp < first_timestamp) {
+        env.storage().instance().set(&FIRST_TIMESTAMP, &timestamp);
+    }
+    let last_timestamp: Option<u64> = env.storage().instance().get(&LAST_TIMESTAMP);
+    if last_timestamp.is_none_or(|last_timestamp| timestamp > last_timestamp) {
+        env.storage().instance().set(&LAST_TIMESTAMP, &timestamp);
+    }
+
+    // Append to the global registration order
+    env.storage()
+        .persistent()
+        .set(&(ORDER_POSITIONS, document_hash.clone()), &position);
+    env.storage()
+        .persistent()
+        .set(&(ORDER_HASHES, position), &document_hash);
+    env.storage().instance().set(&NEXT_ORDER, &(position + 1));
+
+    // Append to the registration journal, which deletions never touch
+    let journal_seq: u64 = env.storage().instance().get(&JOURNAL_LENGTH).unwrap_or(0);
+    env.storage()
+        .persistent()
+        .set(&(JOURNAL, journal_seq), &(journal_seq, document_hash.clone(), owner.clone()));
+    env.storage().instance().set(&JOURNAL_LENGTH, &(journal_seq + 1));
+
+    append_history(env, &document_hash, HistoryAction::Registered, actor);
+
+    // Emit event
+    publish_event(
+        env,
+        EventPriority::Critical,
+        symbol_short!("DOC_REG"),
+        DocumentRegisteredEvent {
+            document_hash: document_hash.clone(),
+            document_name,
+            registered_by: owner.clone(),
+            timestamp,
+        },
+    );
+
+    call_registration_hook(env, &document_hash, owner);
+
+    Ok(record.seq)
+}
+
+/// Delete the listed documents owned by `owner`, skipping any that are missing,
+/// owned by someone else or time-locked. Returns how many were deleted.
+fn delete_documents(env: &Env, owner: &Address, hashes: &Vec<String>) -> u32 {
+    let count = load_document_count(env);
+    let mut documents = load_documents(env);
+
+    let user_docs_key = (USER_DOCS, owner.clone());
+    let mut user_docs = load_user_docs(env, owner);
+
+    let mut ref_index = load_ref_index(env);
+    let mut name_counts = load_name_counts(env);
+    let timestamp = env.ledger().timestamp();
+    let mut deleted = 0u32;
+
+    for document_hash in hashes.iter() {
+        // Skip documents that are missing, owned by someone else, still
+        // locked or sealed. A hash listed twice is only found the first time,
+        // so it is counted once.
+        let record = match documents.get(document_hash.clone()) {
+            Some(record)
+                if record.registered_by == *owner && !is_locked(env, &record) && record.seal.is_none() =>
+            {
+                record
+            }
+            _ => continue,
+        };
+        if let Some(external_ref) = &record.external_ref {
+            ref_index.remove(external_ref.clone());
+        }
+        release_name(&mut name_counts, &record.document_name);
+
+        documents.remove(document_hash.clone());
+        update_reverse_relations(env, &document_hash, None);
+        env.storage()
+            .persistent()
+            .remove(&(RELATIONS, document_hash.clone()));
+        env.storage()
+            .persistent()
+            .remove(&(RELATION_SOURCES, document_hash.clone()));
+        update_reverse_links(env, &document_hash, None);
+        env.storage()
+            .persistent()
+            .remove(&(LINKS, document_hash.clone()));
+        env.storage()
+            .persistent()
+            .remove(&(LINK_AUTHORS, document_hash.clone()));
+        update_tag_index(env, &document_hash, None);
+        env.storage()
+            .persistent()
+            .remove(&(TAGS, document_hash.clone()));
+        env.storage()
+            .persistent()
+            .remove(&(NOTES, document_hash.clone()));
+        env.storage()
+            .persistent()
+            .remove(&(DEPENDENCIES, document_hash.clone()));
+        env.storage()
+            .persistent()
+            .remove(&(PROOF_CHALLENGES, owner.clone(), document_hash.clone()));
+        if let Some(template) = &record.template {
+            let instances_key = (TEMPLATE_INSTANCES, template.clone());
+            let mut instances: Vec<String> = env
+                .storage()
+                .persistent()
+                .get(&instances_key)
+                .unwrap_or(Vec::new(env));
+            if let Some(index) = instances.first_index_of(document_hash.clone()) {
+                instances.remove(index);
+                env.storage().persistent().set(&instances_key, &instances);
+            }
+        }
+        if record.is_template {
+            env.storage()
+                .persistent()
+                .remove(&(TEMPLATE_INSTANCES, document_hash.clone()));
+        }
+        let position_key = (ORDER_POSITIONS, document_hash.clone());
+        let position: Option<u32> = env.storage().persistent().get(&position_key);
+        if let Some(position) = position {
+            env.storage().persistent().remove(&(ORDER_HASHES, position));
+            env.storage().persistent().remove(&position_key);
+        }
+        if let Some(index) = user_docs.first_index_of(document_hash.clone()) {
+            user_docs.remove(index);
+        }
+        env.storage().persistent().set(
+            &(TOMBSTONES, document_hash.clone()),
+            &Tombstone {
+                document_hash: document_hash.clone(),
+                deleted_at: timestamp,
+                deleted_by: owner.clone(),
+            },
+        );
+        deleted += 1;
+
+        append_history(env, &document_hash, HistoryAction::Deleted, owner);
+
+        publish_event(
+            env,
+            EventPriority::Critical,
+            symbol_short!("DOC_DEL"),
+            DocumentDeletedEvent {
+                document_hash,
+                deleted_by: owner.clone(),
+                timestamp,
+            },
+        );
+    }
+
+    if deleted == 0 {
+        return 0;
+    }
+
+    // Persist every change together so the count always matches the set
+    env.storage().persistent().set(&DOCUMENTS, &documents);
+    env.storage().persistent().set(&REF_INDEX, &ref_index);
+    env.storage().persistent().set(&NAME_COUNTS, &name_counts);
+    env.storage().persistent().set(&user_docs_key, &user_docs);
+
+    env.storage()
+        .instance()
+        .set(&DOC_COUNT, &count.saturating_sub(deleted as u64));
+
+    deleted
+}
+
+/// Whether a document is still inside its no-revocation window
+fn is_locked(env: &Env, record: &DocumentRecord) -> bool {
+    compute_status(record, env.ledger().timestamp()) == DocumentStatus::Locked
+}
+
+/// Derive a document's status at time `now`. This is the single place that
+/// decides how the status conditions combine; precedence is Locked > Active.
+fn compute_status(record: &DocumentRecord, now: u64) -> DocumentStatus {
+    match record.revocable_after {
+        Some(revocable_after) if now < revocable_after => DocumentStatus::Locked,
+        _ => DocumentStatus::Active,
+    }
+}
+
+/// Build the verification result for an optional record
+fn document_info(env: &Env, record: Option<DocumentRecord>) -> DocumentInfo {
+    match record {
+        Some(record) => DocumentInfo {
+            exists: true,
+            status: compute_status(&record, env.ledger().timestamp()),
+            record: Vec::from_array(env, [record]),
+            tombstone: Vec::new(env),
+        },
+        None => DocumentInfo {
+            exists: false,
+            record: Vec::new(env),
+            status: DocumentStatus::NotFound,
+            tombstone: Vec::new(env),
+        },
+    }
+}
+
+/// Check that `admin` is the stored contract admin and has authorized the call
+fn require_admin(env: &Env, admin: &Address) -> Result<(), ContractError> {
+    let stored: Address = env
+        .storage()
+        .instance()
+        .get(&ADMIN)
+        .ok_or(ContractError::NotInitialized)?;
+    if stored != *admin {
+        return Err(ContractError::Unauthorized);
+    }
+    admin.require_auth();
+    Ok(())
+}
+
+/// When user lists are private, require `viewer` to authorize and be either
+/// `owner` or the admin, failing with `Unauthorized` otherwise. Listings that
+/// span every owner pass `None` as the owner and are then admin-only.
+fn require_list_access(
+    env: &Env,
+    viewer: &Option<Address>,
+    owner: Option<&Address>,
+) -> Result<(), ContractError> {
+    if !DocumentVerificationContract::are_user_lists_private(env.clone()) {
+        return Ok(());
+    }
+    let viewer = viewer.as_ref().ok_or(ContractError::Unauthorized)?;
+    viewer.require_auth();
+    let admin: Option<Address> = env.storage().instance().get(&ADMIN);
+    if owner != Some(viewer) && admin.as_ref() != Some(viewer) {
+        return Err(ContractError::Unauthorized);
+    }
+    Ok(())
+}
+
+/// When user lists are private, require `user`'s own authorization. Used by
+/// the original per-user reads, which take no viewer.
+fn require_user_auth_if_private(env: &Env, user: &Address) {
+    if DocumentVerificationContract::are_user_lists_private(env.clone()) {
+        user.require_auth();
+    }
+}
+
+/// Load the records of a user's documents in the order of the user's list
+fn load_user_records(env: &Env, user: &Address) -> Vec<DocumentRecord> {
+    let documents = load_documents(env);
+
+    let mut result = Vec::new(env);
+    for hash in load_user_docs(env, user).iter() {
+        if let Some(record) = documents.get(hash) {
+            result.push_back(record);
+        }
+    }
+
+    result
+}
+
+/// Normalize a name for comparison by trimming it and collapsing every run of
+/// ASCII whitespace into a single space. The name must already be validated
+/// against `MAX_NAME_LENGTH`.
+fn normalize_name(name: &String) -> ([u8; MAX_NAME_LENGTH as usize], usize) {
+    let mut raw = [0u8; MAX_NAME_LENGTH as usize];
+    let len = name.len() as usize;
+    name.copy_into_slice(&mut raw[..len]);
+
+    let mut normalized = [0u8; MAX_NAME_LENGTH as usize];
+    let mut normalized_len = 0;
+    let mut pending_space = false;
+    for &byte in &raw[..len] {
+        if byte.is_ascii_whitespace() {
+            pending_space = normalized_len > 0;
+            continue;
+        }
+        if pending_space {
+            normalized[normalized_len] = b' ';
+            normalized_len += 1;
+            pending_space = false;
+        }
+        normalized[normalized_len] = byte;
+        normalized_len += 1;
+    }
+
+    (normalized, normalized_len)
+}
+
+/// Copy a string's UTF-8 bytes into `Bytes`. The string must already be
+/// validated to be at most `MAX_STRING_BYTES` long.
+fn string_to_bytes(env: &Env, value: &String) -> Bytes {
+    let mut buffer = [0u8; MAX_STRING_BYTES];
+    let len = value.len() as usize;
+    value.copy_into_slice(&mut buffer[..len]);
+    Bytes::from_slice(env, &buffer[..len])
+}
+
+/// Strip a leading `0x` or `0X` from a hash that is otherwise the expected
+/// length, so prefixed and bare hashes map to the same canonical form
+fn canonical_hash(env: &Env, document_hash: String) -> String {
+    if document_hash.len() != HASH_LENGTH + 2 {
+        return document_hash;
+    }
+    let mut prefixed = [0u8; HASH_LENGTH as usize + 2];
+    document_hash.copy_into_slice(&mut prefixed);
+    match &prefixed[..2] {
+        b"0x" | b"0X" => String::from_bytes(env, &prefixed[2..]),
+        _ => document_hash,
+    }
+}
+
+/// Canonicalize every hash in a list, keeping the order
+fn canonical_hashes(env: &Env, hashes: Vec<String>) -> Vec<String> {
+    let mut result = Vec::new(env);
+    for document_hash in hashes.iter() {
+        result.push_back(canonical_hash(env, document_hash));
+    }
+    result
+}
+
+/// Decode a 64-character hex document hash into its 32 raw bytes
+fn decode_hash(document_hash: &String) -> Option<[u8; 32]> {
+    if document_hash.len() != HASH_LENGTH {
+        return None;
+    }
+    let mut hex = [0u8; HASH_LENGTH as usize];
+    document_hash.copy_into_slice(&mut hex);
+
+    let mut raw = [0u8; 32];
+    for (index, pair) in hex.chunks_exact(2).enumerate() {
+        let high = (pair[0] as char).to_digit(16)?;
+        let low = (pair[1] as char).to_digit(16)?;
+        raw[index] = (high * 16 + low) as u8;
+    }
+    Some(raw)
+}
+
+/// Name given to a document registered without one: "doc_" followed by the
+/// first 8 characters of its hash
+fn default_document_name(env: &Env, document_hash: &String) -> String {
+    let mut hash = [0u8; HASH_LENGTH as usize];
+    document_hash.copy_into_slice(&mut hash);
+    let mut name = [0u8; 12];
+    name[..4].copy_from_slice(b"doc_");
+    name[4..].copy_from_slice(&hash[..8]);
+    String::from_bytes(env, &name)
+}
+
+/// SHA-256 digest of a document name's UTF-8 bytes
+fn hash_name(env: &Env, document_name: &String) -> BytesN<32> {
+    env.crypto()
+        .sha256(&string_to_bytes(env, document_name))
+        .into()
+}
+
+/// Whether two names are equal once whitespace is normalized
+fn names_collide(a: &String, b: &String) -> bool {
+    let (a, a_len) = normalize_name(a);
+    let (b, b_len) = normalize_name(b);
+    a[..a_len] == b[..b_len]
+}
+
+#[contract]
+pub struct DocumentVerificationContract;
+
+#[contractimpl]
+impl DocumentVerificationContract {
+    /// Initialize the contract with its admin
+    pub fn initialize(env: Env, admin: Address) -> Result<(), ContractError> {
+        if env.storage().instance().has(&ADMIN) {
+            return Err(ContractError::AlreadyInitialized);
+        }
+        admin.require_auth();
+
+        env.storage().instance().set(&ADMIN, &admin);
+
+        // Set initial document count to 0, keeping any existing count
+        if !env.storage().instance().has(&DOC_COUNT) {
+            env.storage().instance().set(&DOC_COUNT, &0u64);
+        }
+
+        Ok(())
+    }
+
+    /// Initialize the contract with its admin and apply every setting in
+    /// `config`. Nothing is stored unless the whole config is valid.
+    pub fn initialize_with_config(env: Env, admin: Address, config: ContractConfig) -> Result<(), ContractError> {
+        check_config(&config)?;
+
+        Self::initialize(env.clone(), admin)?;
+
+        let storage = env.storage().instance();
+        storage.set(&STRICT_NAMES, &config.strict_names);
+        storage.set(&GLOBAL_NAME_UNIQUE, &config.global_name_unique);
+        storage.set(&EVENT_MODE, &config.event_mode);
+        storage.set(&MAX_BATCH_SIZE, &config.max_batch_size);
+        storage.set(&PRIVATE_USER_LISTS, &config.private_user_lists);
+        storage.set(&MIN_REGISTRATION_INTERVAL, &config.min_registration_interval);
+        storage.set(&REGISTRATION_COOLDOWN, &config.registration_cooldown);
+
+        Ok(())
+    }
+
+    /// Propose a new admin, who must accept before the transfer takes effect
+    pub fn propose_admin(env: Env, admin: Address, new_admin: Address) -> Result<(), ContractError> {
+        require_admin(&env, &admin)?;
+        env.storage().instance().set(&PENDING_ADMIN, &new_admin);
+        Ok(())
+    }
+
+    /// Accept a pending admin proposal, completing the transfer
+    pub fn accept_admin(env: Env, new_admin: Address) -> Result<(), ContractError> {
+        let pending: Address = env
+            .storage()
+            .instance()
+            .get(&PENDING_ADMIN)
+            .ok_or(ContractError::NoPendingAdmin)?;
+        if pending != new_admin {
+            return Err(ContractError::Unauthorized);
+        }
+        new_admin.require_auth();
+
+        let previous_admin: Address = env
+            .storage()
+            .instance()
+            .get(&ADMIN)
+            .ok_or(ContractError::NotInitialized)?;
+        env.storage().instance().set(&ADMIN, &new_admin);
+        env.storage().instance().remove(&PENDING_ADMIN);
+
+        // Emit event
+        publish_event(
+            &env,
+            EventPriority::Critical,
+            symbol_short!("ADM_XFER"),
+            AdminTransferredEvent {
+                previous_admin,
+                new_admin,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Cancel a pending admin proposal
+    pub fn cancel_admin_proposal(env: Env, admin: Address) -> Result<(), ContractError> {
+        require_admin(&env, &admin)?;
+        if !env.storage().instance().has(&PENDING_ADMIN) {
+            return Err(ContractError::NoPendingAdmin);
+        }
+        env.storage().instance().remove(&PENDING_ADMIN);
+        Ok(())
+    }
+
+    /// Get the admin awaiting acceptance, if any
+    pub fn get_pending_admin(env: Env) -> Option<Address> {
+        env.storage().instance().get(&PENDING_ADMIN)
+    }
+
+    /// Enable or disable whitespace-insensitive duplicate name checks
+    pub fn set_strict_names(env: Env, admin: Address, enabled: bool) -> Result<(), ContractError> {
+        require_admin(&env, &admin)?;
+        env.storage().instance().set(&STRICT_NAMES, &enabled);
+        Ok(())
+    }
+
+    /// Set which events the contract publishes
+    pub fn set_event_mode(env: Env, admin: Address, mode: EventMode) -> Result<(), ContractError> {
+        require_admin(&env, &admin)?;
+        env.storage().instance().set(&EVENT_MODE, &mode);
+        Ok(())
+    }
+
+    /// Get which events the contract publishes
+    pub fn get_event_mode(env: Env) -> EventMode {
+        env.storage()
+            .instance()
+            .get(&EVENT_MODE)
+            .unwrap_or(EventMode::Full)
+    }
+
+    /// Set the largest number of items a batch operation accepts, between 1
+    /// and `MAX_BATCH_SIZE_LIMIT`
+    pub fn set_max_batch_size(env: Env, admin: Address, max_batch_size: u32) -> Result<(), ContractError> {
+        require_admin(&env, &admin)?;
+        if max_batch_size == 0 || max_batch_size > MAX_BATCH_SIZE_LIMIT {
+            return Err(ContractError::InvalidConfig);
+        }
+        env.storage().instance().set(&MAX_BATCH_SIZE, &max_batch_size);
+        Ok(())
+    }
+
+    /// Get the largest number of items a batch operation accepts
+    pub fn get_max_batch_size(env: Env) -> u32 {
+        env.storage()
+            .instance()
+            .get(&MAX_BATCH_SIZE)
+            .unwrap_or(DEFAULT_MAX_BATCH_SIZE)
+    }
+
+    /// Require document names to be unique across all users
+    pub fn set_global_name_unique(env: Env, admin: Address, enabled: bool) -> Result<(), ContractError> {
+        require_admin(&env, &admin)?;
+        env.storage().instance().set(&GLOBAL_NAME_UNIQUE, &enabled);
+        Ok(())
+    }
+
+    /// Whether document names must be unique across all users
+    pub fn is_global_name_unique(env: Env) -> bool {
+        env.storage()
+            .instance()
+            .get(&GLOBAL_NAME_UNIQUE)
+            .unwrap_or(false)
+    }
+
+    /// Clear the tombstone of a deleted document so its hash can be registered
+    /// again, and lookups report it as never registered
+    pub fn clear_tombstone(env: Env, admin: Address, document_hash: String) -> Result<(), ContractError> {
+        require_admin(&env, &admin)?;
+        let document_hash = canonical_hash(&env, document_hash);
+        env.storage()
+            .persistent()
+            .remove(&(TOMBSTONES, document_hash));
+        Ok(())
+    }
+
+    /// Blocklist a hash so it can no longer be registered or rekeyed to.
+    /// A document already registered under the hash is left in place.
+    pub fn blocklist_hash(env: Env, admin: Address, document_hash: String) -> Result<(), ContractError> {
+        require_admin(&env, &admin)?;
+        let document_hash = canonical_hash(&env, document_hash);
+        if document_hash.len() != HASH_LENGTH {
+            return Err(ContractError::InvalidHashLength);
+        }
+        env.storage()
+            .persistent()
+            .set(&(BLOCKLIST, document_hash), &true);
+        Ok(())
+    }
+
+    /// Remove a hash from the blocklist
+    pub fn unblocklist_hash(env: Env, admin: Address, document_hash: String) -> Result<(), ContractError> {
+        require_admin(&env, &admin)?;
+        let document_hash = canonical_hash(&env, document_hash);
+        env.storage()
+            .persistent()
+            .remove(&(BLOCKLIST, document_hash));
+        Ok(())
+    }
+
+    /// Check whether a hash is blocklisted
+    pub fn is_blocklisted(env: Env, document_hash: String) -> bool {
+        is_hash_blocklisted(&env, &canonical_hash(&env, document_hash))
+    }
+
+    /// Make document listings private. While enabled, listing a user's
+    /// documents requires the `viewer` to be that user or the admin, and
+    /// listings across all owners are admin-only. Per-user reads that take no
+    /// viewer require the user's own authorization instead. Off by default.
+    pub fn set_private_user_lists(env: Env, admin: Address, enabled: bool) -> Result<(), ContractError> {
+        require_admin(&env, &admin)?;
+        env.storage().instance().set(&PRIVATE_USER_LISTS, &enabled);
+        Ok(())
+    }
+
+    /// Check whether per-user document listings are private
+    pub fn are_user_lists_private(env: Env) -> bool {
+        env.storage()
+            .instance()
+            .get(&PRIVATE_USER_LISTS)
+            .unwrap_or(false)
+    }
+
+    /// Set the minimum number of seconds between two registrations by the same
+    /// owner, at most `MAX_REGISTRATION_INTERVAL`. 0 disables the limit.
+    pub fn set_min_registration_interval(env: Env, admin: Address, seconds: u64) -> Result<(), ContractError> {
+        require_admin(&env, &admin)?;
+        if seconds > MAX_REGISTRATION_INTERVAL {
+            return Err(ContractError::InvalidConfig);
+        }
+        env.storage()
+            .instance()
+            .set(&MIN_REGISTRATION_INTERVAL, &seconds);
+        Ok(())
+    }
+
+    /// Get the minimum number of seconds between two registrations by the same owner
+    pub fn get_min_registration_interval(env: Env) -> u64 {
+        env.storage()
+            .instance()
+            .get(&MIN_REGISTRATION_INTERVAL)
+            .unwrap_or(0)
+    }
+
+    /// Set the contract notified after every registration. With `fail_open` a
+    /// failing hook is ignored; otherwise it aborts the registration.
+    pub fn set_registration_hook(env: Env, admin: Address, hook: Address, fail_open: bool) -> Result<(), ContractError> {
+        require_admin(&env, &admin)?;
+        env.storage().instance().set(
+            &REGISTRATION_HOOK,
+            &RegistrationHook {
+                contract: hook,
+                fail_open,
+            },
+        );
+        Ok(())
+    }
+
+    /// Stop notifying a registration hook
+    pub fn remove_registration_hook(env: Env, admin: Address) -> Result<(), ContractError> {
+        require_admin(&env, &admin)?;
+        env.storage().instance().remove(&REGISTRATION_HOOK);
+        Ok(())
+    }
+
+    /// Get the registration hook, if one is set
+    pub fn get_registration_hook(env: Env) -> Option<RegistrationHook> {
+        env.storage().instance().get(&REGISTRATION_HOOK)
+    }
+
+    /// Set the number of ledgers that must close between two registrations by
+    /// the same owner, at most `MAX_REGISTRATION_COOLDOWN`. 0 disables the
+    /// cooldown.
+    pub fn set_registration_cooldown(env: Env, admin: Address, ledgers: u32) -> Result<(), ContractError> {
+        require_admin(&env, &admin)?;
+        if ledgers > MAX_REGISTRATION_COOLDOWN {
+            return Err(ContractError::InvalidConfig);
+        }
+        env.storage().instance().set(&REGISTRATION_COOLDOWN, &ledgers);
+        Ok(())
+    }
+
+    /// Get the number of ledgers between two registrations by the same owner
+    pub fn get_registration_cooldown(env: Env) -> u32 {
+        env.storage()
+            .instance()
+            .get(&REGISTRATION_COOLDOWN)
+            .unwrap_or(0)
+    }
+
+    /// Get how many more ledgers must close before `user` may register again
+    pub fn get_user_cooldown_remaining(env: Env, user: Address) -> u32 {
+        let cooldown = Self::get_registration_cooldown(env.clone());
+        if cooldown == 0 {
+            return 0;
+        }
+        let last_ledger: Option<u32> = env
+            .storage()
+            .persistent()
+            .get(&(LAST_REGISTRATION_LEDGER, user));
+        match last_ledger {
+            Some(last_ledger) => last_ledger
+                .saturating_add(cooldown)
+                .saturating_sub(env.ledger().sequence()),
+            None => 0,
+        }
+    }
+
+    /// Pause or resume one kind of operation
+    pub fn set_operation_paused(
+        env: Env,
+        admin: Address,
+        operation: OperationKind,
+        paused: bool,
+    ) -> Result<(), ContractError> {
+        require_admin(&env, &admin)?;
+        let mut paused_operations: Map<OperationKind, bool> = env
+            .storage()
+            .instance()
+            .get(&PAUSED_OPERATIONS)
+            .unwrap_or(Map::new(&env));
+        if paused {
+            paused_operations.set(operation, true);
+        } else {
+            paused_operations.remove(operation);
+        }
+        env.storage()
+            .instance()
+            .set(&PAUSED_OPERATIONS, &paused_operations);
+        Ok(())
+    }
+
+    /// Check whether one kind of operation is paused
+    pub fn is_operation_paused(env: Env, operation: OperationKind) -> bool {
+        let paused_operations: Map<OperationKind, bool> = env
+            .storage()
+            .instance()
+            .get(&PAUSED_OPERATIONS)
+            .unwrap_or(Map::new(&env));
+        paused_operations.get(operation).unwrap_or(false)
+    }
+
+    /// Register a new document, returning its sequence number
+    pub fn register_document(
+        env: Env,
+        caller: Address,
+        document_hash: String,
+        document_name: String,
+    ) -> Result<u64, ContractError> {
+        Self::register_document_with_options(
+            env,
+            caller,
+            document_hash,
+            document_name,
+            default_options(),
+        )
+    }
+
+    /// Register a short-lived document in temporary storage, kept for at least
+    /// `ttl_ledgers` ledgers. Temporary entries cost less rent but are deleted
+    /// by the network once their TTL runs out and cannot be restored. They
+    /// are validated, throttled and reported to the registration hook like any
+    /// other registration and are verifiable like any other document, but are
+    /// not counted, listed per user, indexed by name, or deletable by their
+    /// owner. Their names are checked against the registry's names but do not
+    /// reserve them.
+    pub fn register_document_temporary(
+        env: Env,
+        caller: Address,
+        document_hash: String,
+        document_name: String,
+        ttl_ledgers: u32,
+    ) -> Result<(), ContractError> {
+        // Require caller authorization
+        caller.require_auth();
+
+        if ttl_ledgers == 0 || ttl_ledgers > env.storage().max_ttl() {
+            return Err(ContractError::InvalidTtl);
+        }
+
+        store_document(
+            &env,
+            &caller,
+            &caller,
+            document_hash,
+            document_name,
+            default_options(),
+            RecordOrigin {
+                temporary_ttl: Some(ttl_ledgers),
+                ..Default::default()
+            },
+        )?;
+
+        Ok(())
+    }
+
+    /// Register a new document with optional settings
+    pub fn register_document_with_options(
+        env: Env,
+        caller: Address,
+        document_hash: String,
+        document_name: String,
+        options: RegistrationOptions,
+    ) -> Result<u64, ContractError> {
+        // Require caller authorization
+        caller.require_auth();
+
+        store_document(
+            &env,
+            &caller,
+            &caller,
+            document_hash,
+            document_name,
+            options,
+            RecordOrigin::default(),
+        )
+    }
+
+    /// Import a document from another system with its original registration
+    /// time and block number (admin only). The record is flagged `imported`,
+    /// and imports are exempt from registration throttling.
+    pub fn admin_import_document(
+        env: Env,
+        admin: Address,
+        owner: Address,
+        document_hash: String,
+        document_name: String,
+        original_timestamp: u64,
+        original_block: u32,
+    ) -> Result<u64, ContractError> {
+        require_admin(&env, &admin)?;
+
+        if original_timestamp > env.ledger().timestamp() || original_block > env.ledger().sequence() {
+            return Err(ContractError::InvalidImportTime);
+        }
+
+        store_document(
+            &env,
+            &owner,
+            &admin,
+            document_hash,
+            document_name,
+            default_options(),
+            RecordOrigin {
+                imported_at: Some((original_timestamp, original_block)),
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Register a document as a template that others can register filled-in
+    /// instances of
+    pub fn register_template(
+        env: Env,
+        caller: Address,
+        document_hash: String,
+        document_name: String,
+    ) -> Result<u64, ContractError> {
+        // Require caller authorization
+        caller.require_auth();
+
+        store_document(
+            &env,
+            &caller,
+            &caller,
+            document_hash,
+            document_name,
+            default_options(),
+            RecordOrigin {
+                is_template: true,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Register a document as an instance of a template. Anyone may register
+    /// from any template; an instance cannot itself be used as a template.
+    pub fn register_from_template(
+        env: Env,
+        caller: Address,
+        template_hash: String,
+        instance_hash: String,
+        document_name: String,
+    ) -> Result<u64, ContractError> {
+        // Require caller authorization
+        caller.require_auth();
+
+        let template_hash = canonical_hash(&env, template_hash);
+        let template = find_document(&env, &load_documents(&env), &template_hash)
+            .ok_or(ContractError::DocumentNotFound)?;
+        if !template.is_template {
+            return Err(ContractError::NotATemplate);
+        }
+
+        store_document(
+            &env,
+            &caller,
+            &caller,
+            instance_hash,
+            document_name,
+            default_options(),
+            RecordOrigin {
+                template: Some(template_hash),
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Register a document only if `requires_hash` is registered and active,
+    /// failing with `RequiredDocumentMissing` otherwise, including while the
+    /// required document is still time-locked. The dependency is
+    /// stored with the new document and reported by `get_dependencies`. It
+    /// keeps the required hash as it was at registration time.
+    pub fn register_document_requiring(
+        env: Env,
+        caller: Address,
+        document_hash: String,
+        document_name: String,
+        requires_hash: String,
+    ) -> Result<u64, ContractError> {
+        // Require caller authorization
+        caller.require_auth();
+
+        let document_hash = canonical_hash(&env, document_hash);
+        let requires_hash = canonical_hash(&env, requires_hash);
+        if requires_hash == document_hash {
+            return Err(ContractError::InvalidRelation);
+        }
+        let required_active = find_document(&env, &load_documents(&env), &requires_hash)
+            .is_some_and(|record| compute_status(&record, env.ledger().timestamp()) == DocumentStatus::Active);
+        if !required_active {
+            return Err(ContractError::RequiredDocumentMissing);
+        }
+
+        let seq = store_document(
+            &env,
+            &caller,
+            &caller,
+            document_hash.clone(),
+            document_name,
+            default_options(),
+            RecordOrigin::default(),
+        )?;
+        env.storage().persistent().set(
+            &(DEPENDENCIES, document_hash),
+            &Vec::from_array(&env, [requires_hash]),
+        );
+
+        Ok(seq)
+    }
+
+    /// Get the hashes a document had to have registered before it
+    pub fn get_dependencies(env: Env, document_hash: String) -> Vec<String> {
+        env.storage()
+            .persistent()
+            .get(&(DEPENDENCIES, canonical_hash(&env, document_hash)))
+            .unwrap_or(Vec::new(&env))
+    }
+
+    /// Get the documents registered from a template, in registration order
+    pub fn get_template_instances(
+        env: Env,
+        viewer: Option<Address>,
+        template_hash: String,
+    ) -> Result<Vec<DocumentRecord>, ContractError> {
+        let template_hash = canonical_hash(&env, template_hash);
+        require_list_access(&env, &viewer, None)?;
+
+        let instances: Vec<String> = env
+            .storage()
+            .persistent()
+            .get(&(TEMPLATE_INSTANCES, template_hash))
+            .unwrap_or(Vec::new(&env));
+
+        let documents = load_documents(&env);
+        let mut result = Vec::new(&env);
+        for instance_hash in instances.iter() {
+            if let Some(record) = documents.get(instance_hash) {
+                result.push_back(record);
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Allow `delegate` to register up to `max_docs` documents on the owner's behalf
+    pub fn set_delegate(env: Env, owner: Address, delegate: Address, max_docs: u32) {
+        owner.require_auth();
+        env.storage()
+            .persistent()
+            .set(&(DELEGATES, owner, delegate), &max_docs);
+    }
+
+    /// Revoke a delegate's registration rights
+    pub fn remove_delegate(env: Env, owner: Address, delegate: Address) -> Result<(), ContractError> {
+        owner.require_auth();
+        let delegate_key = (DELEGATES, owner, delegate);
+        if !env.storage().persistent().has(&delegate_key) {
+            return Err(ContractError::NotADelegate);
+        }
+        env.storage().persistent().remove(&delegate_key);
+        Ok(())
+    }
+
+    /// Register a document for the account of `owner_pubkey` using an ed25519
+    /// signature instead of Soroban authorization, so anyone can relay it. The
+    /// signed message is the XDR of the tuple (this contract's address,
+    /// `document_hash`, `document_name`, `nonce`), and `nonce` must equal
+    /// `get_signature_nonce(owner_pubkey)`. Panics if the signature is invalid.
+    pub fn register_document_with_sig(
+        env: Env,
+        owner_pubkey: BytesN<32>,
+        document_hash: String,
+        document_name: String,
+        nonce: u64,
+        signature: BytesN<64>,
+    ) -> Result<u64, ContractError> {
+        let nonce_key = (SIGNATURE_NONCES, owner_pubkey.clone());
+        let expected_nonce: u64 = env.storage().persistent().get(&nonce_key).unwrap_or(0);
+        if nonce != expected_nonce {
+            return Err(ContractError::InvalidNonce);
+        }
+
+        let message = (
+            env.current_contract_address(),
+            document_hash.clone(),
+            document_name.clone(),
+            nonce,
+        )
+            .to_xdr(&env);
+        env.crypto().ed25519_verify(&owner_pubkey, &message, &signature);
+
+        let owner = account_address(&env, &owner_pubkey);
+        let count = store_document(
+            &env,
+            &owner,
+            &owner,
+            document_hash,
+            document_name,
+            default_options(),
+            RecordOrigin::default(),
+        )?;
+        env.storage().persistent().set(&nonce_key, &(nonce + 1));
+
+        Ok(count)
+    }
+
+    /// Get the nonce the next signed registration for `owner_pubkey` must use
+    pub fn get_signature_nonce(env: Env, owner_pubkey: BytesN<32>) -> u64 {
+        env.storage()
+            .persistent()
+            .get(&(SIGNATURE_NONCES, owner_pubkey))
+            .unwrap_or(0)
+    }
+
+    /// Register a document owned by `owner`, spending one of the delegate's allowance
+    pub fn register_as_delegate(
+        env: Env,
+        delegate: Address,
+        owner: Address,
+        document_hash: String,
+        document_name: String,
+    ) -> Result<u64, ContractError> {
+        // Require delegate authorization
+        delegate.require_auth();
+
+        let delegate_key = (DELEGATES, owner.clone(), delegate.clone());
+        let remaining: u32 = env
+            .storage()
+            .persistent()
+            .get(&delegate_key)
+            .ok_or(ContractError::NotADelegate)?;
+        if remaining == 0 {
+            return Err(ContractError::DelegateLimitReached);
+        }
+
+        let count = store_document(
+            &env,
+            &owner,
+            &delegate,
+            document_hash,
+            document_name,
+            default_options(),
+            RecordOrigin::default(),
+        )?;
+        env.storage()
+            .persistent()
+            .set(&delegate_key, &(remaining - 1));
+
+        Ok(count)
+    }
+
+    /// Delete several documents owned by the caller, returning how many were
+    /// deleted. Missing, foreign, locked and sealed documents are skipped.
+    pub fn delete_documents_batch(
+        env: Env,
+        caller: Address,
+        hashes: Vec<String>,
+    ) -> Result<u32, ContractError> {
+        // Require caller authorization
+        caller.require_auth();
+
+        check_not_paused(&env, OperationKind::Delete)?;
+
+        check_batch_size(&env, hashes.len())?;
+
+        let hashes = canonical_hashes(&env, hashes);
+        Ok(delete_documents(&env, &caller, &hashes))
+    }
+
+    /// Delete a document owned by the caller, leaving a tombstone on its hash
+    pub fn delete_document(env: Env, caller: Address, document_hash: String) -> Result<(), ContractError> {
+        // Require caller authorization
+        caller.require_auth();
+
+        check_not_paused(&env, OperationKind::Delete)?;
+
+        let document_hash = canonical_hash(&env, document_hash);
+        let record = load_documents(&env)
+            .get(document_hash.clone())
+            .ok_or(ContractError::DocumentNotFound)?;
+        if record.registered_by != caller {
+            return Err(ContractError::NotDocumentOwner);
+        }
+        if is_locked(&env, &record) {
+            return Err(ContractError::DocumentLocked);
+        }
+        if record.seal.is_some() {
+            return Err(ContractError::DocumentSealed);
+        }
+
+        delete_documents(&env, &caller, &Vec::from_array(&env, [document_hash]));
+
+        Ok(())
+    }
+
+    /// Move a document registered under a wrong hash to the correct hash.
+    /// A time-locked document stays on its hash until the lock expires, and a
+    /// sealed one stays on the hash its seal signs. The history moves with the
+    /// record; the old hash is left with a tombstone and a `Deleted` history
+    /// entry, so it reads as deleted and cannot be silently registered again.
+    pub fn rekey_document(
+        env: Env,
+        caller: Address,
+        old_hash: String,
+        new_hash: String,
+    ) -> Result<(), ContractError> {
+        // Require caller authorization
+        caller.require_auth();
+
+        check_not_paused(&env, OperationKind::Rekey)?;
+
+        let old_hash = canonical_hash(&env, old_hash);
+        let new_hash = canonical_hash(&env, new_hash);
+        if new_hash.len() != HASH_LENGTH {
+            return Err(ContractError::InvalidHashLength);
+        }
+
+        let mut documents = load_documents(&env);
+        let mut record = documents
+            .get(old_hash.clone())
+            .ok_or(ContractError::DocumentNotFound)?;
+        if record.registered_by != caller {
+            return Err(ContractError::NotDocumentOwner);
+        }
+        if is_locked(&env, &record) {
+            return Err(ContractError::DocumentLocked);
+        }
+        if record.seal.is_some() {
+            return Err(ContractError::DocumentSealed);
+        }
+        if is_hash_blocklisted(&env, &new_hash) {
+            return Err(ContractError::HashBlocklisted);
+        }
+        if is_hash_tombstoned(&env, &new_hash) {
+            return Err(ContractError::HashTombstoned);
+        }
+        if find_document(&env, &documents, &new_hash).is_some() {
+            return Err(ContractError::DocumentAlreadyExists);
+        }
+
+        // Move the record itself
+        if let Some(external_ref) = &record.external_ref {
+            let mut ref_index = load_ref_index(&env);
+            ref_index.set(external_ref.clone(), new_hash.clone());
+            env.storage().persistent().set(&REF_INDEX, &ref_index);
+        }
+        record.document_hash = new_hash.clone();
+        if let Some(template) = &record.template {
+            let instances_key = (TEMPLATE_INSTANCES, template.clone());
+            let mut instances: Vec<String> = env
+                .storage()
+                .persistent()
+                .get(&instances_key)
+                .unwrap_or(Vec::new(&env));
+            if let Some(index) = instances.first_index_of(old_hash.clone()) {
+                instances.set(index, new_hash.clone());
+                env.storage().persistent().set(&instances_key, &instances);
+            }
+        }
+        if record.is_template {
+            // Point every instance at the template's new hash
+            let instances: Vec<String> = env
+                .storage()
+                .persistent()
+                .get(&(TEMPLATE_INSTANCES, old_hash.clone()))
+                .unwrap_or(Vec::new(&env));
+            for instance_hash in instances.iter() {
+                if let Some(mut instance) = documents.get(instance_hash.clone()) {
+                    instance.template = Some(new_hash.clone());
+                    documents.set(instance_hash, instance);
+                }
+            }
+            move_document_data::<Vec<String>>(&env, TEMPLATE_INSTANCES, &old_hash, &new_hash);
+        }
+        documents.remove(old_hash.clone());
+        documents.set(new_hash.clone(), record);
+        env.storage().persistent().set(&DOCUMENTS, &documents);
+
+        // Replace the hash in place so the user's ordering is preserved
+        let user_docs_key = (USER_DOCS, caller.clone());
+        let mut user_docs: Vec<String> = env
+            .storage()
+            .persistent()
+            .get(&user_docs_key)
+            .unwrap_or(Vec::new(&env));
+        if let Some(index) = user_docs.first_index_of(old_hash.clone()) {
+            user_docs.set(index, new_hash.clone());
+            env.storage().persistent().set(&user_docs_key, &user_docs);
+        }
+
+        // Carry over the data stored per document, and point links and
+        // relations other documents hold towards the old hash at the new one
+        update_reverse_relations(&env, &old_hash, Some(&new_hash));
+        move_document_data::<Vec<(Symbol, String)>>(&env, RELATIONS, &old_hash, &new_hash);
+        move_document_data::<Vec<String>>(&env, RELATION_SOURCES, &old_hash, &new_hash);
+        update_reverse_links(&env, &old_hash, Some(&new_hash));
+        move_document_data::<Vec<(String, String)>>(&env, LINKS, &old_hash, &new_hash);
+        move_document_data::<Map<(String, String), Address>>(&env, LINK_AUTHORS, &old_hash, &new_hash);
+        update_tag_index(&env, &old_hash, Some(&new_hash));
+        move_document_data::<Vec<String>>(&env, TAGS, &old_hash, &new_hash);
+        move_document_data::<Vec<VerifierNote>>(&env, NOTES, &old_hash, &new_hash);
+        move_document_data::<Vec<String>>(&env, DEPENDENCIES, &old_hash, &new_hash);
+        // A challenge names the old hash, so the owner has to request a new one
+        env.storage()
+            .persistent()
+            .remove(&(PROOF_CHALLENGES, caller.clone(), old_hash.clone()));
+        let position: Option<u32> = env
+            .storage()
+            .persistent()
+            .get(&(ORDER_POSITIONS, old_hash.clone()));
+        if let Some(position) = position {
+            env.storage()
+                .persistent()
+                .set(&(ORDER_HASHES, position), &new_hash);
+        }
+        move_document_data::<u32>(&env, ORDER_POSITIONS, &old_hash, &new_hash);
+        move_document_data::<Vec<HistoryEntry>>(&env, HISTORY, &old_hash, &new_hash);
+        append_history(&env, &new_hash, HistoryAction::Rekeyed, &caller);
+        append_history(&env, &old_hash, HistoryAction::Deleted, &caller);
+        env.storage().persistent().set(
+            &(TOMBSTONES, old_hash.clone()),
+            &Tombstone {
+                document_hash: old_hash.clone(),
+                deleted_at: env.ledger().timestamp(),
+                deleted_by: caller.clone(),
+            },
+        );
+
+        // Emit event
+        publish_event(
+            &env,
+            EventPriority::Critical,
+            symbol_short!("DOC_RKEY"),
+            DocumentRekeyedEvent {
+                old_hash,
+                new_hash,
+                rekeyed_by: caller,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Transfer several documents to a new owner in one all-or-nothing call
+    pub fn transfer_documents_batch(
+        env: Env,
+        caller: Address,
+        hashes: Vec<String>,
+        new_owner: Address,
+    ) -> Result<(), ContractError> {
+        // Require caller authorization
+        caller.require_auth();
+
+        check_not_paused(&env, OperationKind::Transfer)?;
+
+        check_batch_size(&env, hashes.len())?;
+
+        let hashes = canonical_hashes(&env, hashes);
+        // Validate the whole batch before mutating anything
+        let mut documents = load_documents(&env);
+        for document_hash in hashes.iter() {
+            let record = documents
+                .get(document_hash)
+                .ok_or(ContractError::DocumentNotFound)?;
+            if record.registered_by != caller {
+                return Err(ContractError::NotDocumentOwner);
+            }
+        }
+
+        transfer_documents(&env, &mut documents, &caller, &new_owner, &hashes);
+
+        Ok(())
+    }
+
+    /// Move up to `max_items` of `from`'s documents to `to`, returning how many
+    /// were moved. `from` must authorize the merge as well as the caller. In
+    /// strict name mode, documents whose name collides with one already owned
+    /// by `to` stay with `from` and are skipped. `max_items` may not exceed the
+    /// maximum batch size.
+    pub fn merge_user_documents(
+        env: Env,
+        caller: Address,
+        from: Address,
+        to: Address,
+        max_items: u32,
+    ) -> Result<u32, ContractError> {
+        caller.require_auth();
+        if caller != from {
+            from.require_auth();
+        }
+
+        check_not_paused(&env, OperationKind::Transfer)?;
+
+        check_batch_size(&env, max_items)?;
+
+        if from == to {
+            return Ok(0);
+        }
+
+        let mut documents = load_documents(&env);
+        let strict_names: bool = env
+            .storage()
+            .instance()
+            .get(&STRICT_NAMES)
+            .unwrap_or(false);
+        let mut taken_names = Vec::new(&env);
+        if strict_names {
+            for document_hash in load_user_docs(&env, &to).iter() {
+                if let Some(record) = documents.get(document_hash) {
+                    taken_names.push_back(record.document_name);
+                }
+            }
+        }
+
+        let mut hashes = Vec::new(&env);
+        for document_hash in load_user_docs(&env, &from).iter() {
+            if hashes.len() >= max_items {
+                break;
+            }
+            let record = match documents.get(document_hash.clone()) {
+                Some(record) if record.registered_by == from => record,
+                _ => continue,
+            };
+            if strict_names {
+                if taken_names
+                    .iter()
+                    .any(|name| names_collide(&name, &record.document_name))
+                {
+                    continue;
+                }
+                taken_names.push_back(record.document_name);
+            }
+            hashes.push_back(document_hash);
+        }
+
+        transfer_documents(&env, &mut documents, &from, &to, &hashes);
+
+        Ok(hashes.len())
+    }
+
+    /// Transfer a document only if its stored name is exactly `expected_name`.
+    /// The comparison is byte-for-byte; strict-mode normalization is not applied.
+    pub fn transfer_document_if_name(
+        env: Env,
+        caller: Address,
+        document_hash: String,
+        to: Address,
+        expected_name: String,
+    ) -> Result<(), ContractError> {
+        // Require caller authorization
+        caller.require_auth();
+
+        check_not_paused(&env, OperationKind::Transfer)?;
+
+        let document_hash = canonical_hash(&env, document_hash);
+        let mut documents = load_documents(&env);
+        let record = documents
+            .get(document_hash.clone())
+            .ok_or(ContractError::DocumentNotFound)?;
+        if record.registered_by != caller {
+            return Err(ContractError::NotDocumentOwner);
+        }
+        if record.document_name != expected_name {
+            return Err(ContractError::NameMismatch);
+        }
+
+        let hashes = Vec::from_array(&env, [document_hash]);
+        transfer_documents(&env, &mut documents, &caller, &to, &hashes);
+
+        Ok(())
+    }
+
+    /// Verify if a document exists
+    pub fn verify_document(env: Env, document_hash: String) -> DocumentInfo {
+        let document_hash = canonical_hash(&env, document_hash);
+        let record = find_document(&env, &load_documents(&env), &document_hash);
+        let mut info = document_info(&env, record);
+        if !info.exists
+            && let Some(tombstone) = Self::get_tombstone(env.clone(), document_hash)
+        {
+            info.status = DocumentStatus::Deleted;
+            info.tombstone = Vec::from_array(&env, [tombstone]);
+        }
+        info
+    }
+
+    /// Whether a document is currently valid. Documents here are never revoked
+    /// and have no validity window, so any live record, locked or not, is valid.
+    pub fn is_document_valid(env: Env, document_hash: String) -> bool {
+        matches!(
+            Self::verify_document(env, document_hash).status,
+            DocumentStatus::Active | DocumentStatus::Locked
+        )
+    }
+
+    /// Verify a document and also return how many documents its owner holds,
+    /// or 0 when the document is not found
+    pub fn verify_with_issuer_stats(env: Env, document_hash: String) -> (DocumentInfo, u32) {
+        let info = Self::verify_document(env.clone(), document_hash);
+        let owner_count = match info.record.first() {
+            Some(record) => load_user_docs(&env, &record.registered_by).len(),
+            None => 0,
+        };
+        (info, owner_count)
+    }
+
+    /// Verify a document and return only its status as a stable numeric code:
+    /// 0 = not found, 1 = active, 5 = locked, 7 = deleted (tombstoned).
+    /// Codes 2 (revoked), 3 (expired), 4 (frozen) and 6 (superseded) are
+    /// reserved and never returned by this contract.
+    pub fn verify_status(env: Env, document_hash: String) -> u32 {
+        match Self::verify_document(env, document_hash).status {
+            DocumentStatus::NotFound => 0,
+            DocumentStatus::Active => 1,
+            DocumentStatus::Locked => 5,
+            DocumentStatus::Deleted => 7,
+        }
+    }
+
+    /// Get every (owner, name) pair a hash is registered under. Hashes are
+    /// unique across owners in this contract, so this holds at most one entry.
+    pub fn get_name_variants(env: Env, document_hash: String) -> Vec<(Address, String)> {
+        let mut variants = Vec::new(&env);
+        if let Some(record) = Self::verify_document(env, document_hash).record.first() {
+            variants.push_back((record.registered_by, record.document_name));
+        }
+        variants
+    }
+
+    /// Get the tombstone left by deleting a document, if any. Every deletion
+    /// leaves one, so a deleted hash cannot be silently registered again and
+    /// lookups can tell it apart from one that was never registered.
+    /// Tombstones do not count towards the document count.
+    pub fn get_tombstone(env: Env, document_hash: String) -> Option<Tombstone> {
+        let document_hash = canonical_hash(&env, document_hash);
+        env.storage().persistent().get(&(TOMBSTONES, document_hash))
+    }
+
+    /// Link a document owned by the caller to another document with a typed
+    /// relation. A document holds at most `MAX_LINKS` relations and cannot be
+    /// related to itself.
+    pub fn link_documents(
+        env: Env,
+        caller: Address,
+        from_hash: String,
+        to_hash: String,
+        relation: Symbol,
+    ) -> Result<(), ContractError> {
+        // Require caller authorization
+        caller.require_auth();
+
+        check_not_paused(&env, OperationKind::Link)?;
+
+        let from_hash = canonical_hash(&env, from_hash);
+        let to_hash = canonical_hash(&env, to_hash);
+        if from_hash == to_hash {
+            return Err(ContractError::InvalidRelation);
+        }
+        let documents = load_documents(&env);
+        let from_record = documents
+            .get(from_hash.clone())
+            .ok_or(ContractError::DocumentNotFound)?;
+        if find_document(&env, &documents, &to_hash).is_none() {
+            return Err(ContractError::DocumentNotFound);
+        }
+        if from_record.registered_by != caller {
+            return Err(ContractError::NotDocumentOwner);
+        }
+
+        // Relations are stored on the source document, with the source
+        // indexed on the target so either side can be cleaned up
+        let mut relations = load_relations(&env, &from_hash);
+        let relation_entry = (relation.clone(), to_hash.clone());
+        if relations.contains(&relation_entry) {
+            return Ok(());
+        }
+        if relations.len() >= MAX_LINKS {
+            return Err(ContractError::TooManyLinks);
+        }
+        relations.push_back(relation_entry);
+        env.storage()
+            .persistent()
+            .set(&(RELATIONS, from_hash.clone()), &relations);
+        let mut sources = load_relation_sources(&env, &to_hash);
+        if !sources.contains(&from_hash) {
+            sources.push_back(from_hash.clone());
+            env.storage()
+                .persistent()
+                .set(&(RELATION_SOURCES, to_hash.clone()), &sources);
+        }
+
+        append_history(&env, &from_hash, HistoryAction::Linked, &caller);
+
+        // Emit event
+        publish_event(
+            &env,
+            EventPriority::Detail,
+            symbol_short!("DOC_LINK"),
+            DocumentLinkedEvent {
+                from_hash,
+                to_hash,
+                relation,
+                linked_by: caller,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Get the typed relations from a document to other documents
+    pub fn get_related_documents(env: Env, document_hash: String) -> Vec<(Symbol, String)> {
+        let document_hash = canonical_hash(&env, document_hash);
+        load_relations(&env, &document_hash)
+    }
+
+    /// Link two documents in both directions with a relation label. The caller
+    /// must own at least one of them, and history is only written for the ones
+    /// it owns. An owner can remove any link from its document with
+    /// `unlink_documents`.
+    pub fn link_related_documents(
+        env: Env,
+        caller: Address,
+        hash_a: String,
+        hash_b: String,
+        relation: String,
+    ) -> Result<(), ContractError> {
+        // Require caller authorization
+        caller.require_auth();
+
+        check_not_paused(&env, OperationKind::Link)?;
+
+        let hash_a = canonical_hash(&env, hash_a);
+        let hash_b = canonical_hash(&env, hash_b);
+        if relation.is_empty() || relation.len() > MAX_RELATION_LENGTH || hash_a == hash_b {
+            return Err(ContractError::InvalidRelation);
+        }
+
+        let documents = load_documents(&env);
+        let record_a = documents
+            .get(hash_a.clone())
+            .ok_or(ContractError::DocumentNotFound)?;
+        let record_b = documents
+            .get(hash_b.clone())
+            .ok_or(ContractError::DocumentNotFound)?;
+        if record_a.registered_by != caller && record_b.registered_by != caller {
+            return Err(ContractError::NotDocumentOwner);
+        }
+
+        let mut links_a = load_links(&env, &hash_a);
+        let mut links_b = load_links(&env, &hash_b);
+        let entry_a = (hash_b.clone(), relation.clone());
+        if links_a.contains(&entry_a) {
+            return Ok(());
+        }
+        check_link_capacity(&env, &hash_a, &record_a.registered_by, &caller)?;
+        check_link_capacity(&env, &hash_b, &record_b.registered_by, &caller)?;
+        let entry_b = (hash_a.clone(), relation.clone());
+        links_a.push_back(entry_a.clone());
+        links_b.push_back(entry_b.clone());
+        env.storage()
+            .persistent()
+            .set(&(LINKS, hash_a.clone()), &links_a);
+        env.storage()
+            .persistent()
+            .set(&(LINKS, hash_b.clone()), &links_b);
+
+        let mut authors_a = load_link_authors(&env, &hash_a);
+        authors_a.set(entry_a, caller.clone());
+        env.storage()
+            .persistent()
+            .set(&(LINK_AUTHORS, hash_a.clone()), &authors_a);
+        let mut authors_b = load_link_authors(&env, &hash_b);
+        authors_b.set(entry_b, caller.clone());
+        env.storage()
+            .persistent()
+            .set(&(LINK_AUTHORS, hash_b.clone()), &authors_b);
+
+        if record_a.registered_by == caller {
+            append_history(&env, &hash_a, HistoryAction::Linked, &caller);
+        }
+        if record_b.registered_by == caller {
+            append_history(&env, &hash_b, HistoryAction::Linked, &caller);
+        }
+
+        // Emit event
+        publish_event(
+            &env,
+            EventPriority::Detail,
+            symbol_short!("DOC_REL"),
+            DocumentsRelatedEvent {
+                hash_a,
+                hash_b,
+                relation,
+                linked_by: caller,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Remove a link from a document owned by the caller, on both sides.
+    /// Removing a link the document does not hold does nothing.
+    pub fn unlink_documents(
+        env: Env,
+        caller: Address,
+        document_hash: String,
+        linked_hash: String,
+        relation: String,
+    ) -> Result<(), ContractError> {
+        // Require caller authorization
+        caller.require_auth();
+
+        check_not_paused(&env, OperationKind::Link)?;
+
+        let document_hash = canonical_hash(&env, document_hash);
+        let linked_hash = canonical_hash(&env, linked_hash);
+        let record = load_documents(&env)
+            .get(document_hash.clone())
+            .ok_or(ContractError::DocumentNotFound)?;
+        if record.registered_by != caller {
+            return Err(ContractError::NotDocumentOwner);
+        }
+        if !load_links(&env, &document_hash).contains((linked_hash.clone(), relation.clone())) {
+            return Ok(());
+        }
+
+        remove_link(&env, &document_hash, &linked_hash, &relation);
+        remove_link(&env, &linked_hash, &document_hash, &relation);
+
+        append_history(&env, &document_hash, HistoryAction::Unlinked, &caller);
+
+        // Emit event
+        publish_event(
+            &env,
+            EventPriority::Detail,
+            symbol_short!("DOC_UNLNK"),
+            DocumentsUnlinkedEvent {
+                document_hash,
+                linked_hash,
+                relation,
+                unlinked_by: caller,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Get the documents linked to a document as (linked hash, relation) pairs
+    pub fn get_linked_documents(env: Env, document_hash: String) -> Vec<(String, String)> {
+        let document_hash = canonical_hash(&env, document_hash);
+        load_links(&env, &document_hash)
+    }
+
+    /// Attach a freeform tag to a document owned by the caller. Adding a tag
+    /// the document already carries does nothing.
+    pub fn add_tag(
+        env: Env,
+        caller: Address,
+        document_hash: String,
+        tag: String,
+    ) -> Result<(), ContractError> {
+        // Require caller authorization
+        caller.require_auth();
+
+        check_not_paused(&env, OperationKind::Tag)?;
+
+        let document_hash = canonical_hash(&env, document_hash);
+        if tag.is_empty() || tag.len() > MAX_TAG_LENGTH {
+            return Err(ContractError::InvalidTag);
+        }
+
+        let record = load_documents(&env)
+            .get(document_hash.clone())
+            .ok_or(ContractError::DocumentNotFound)?;
+        if record.registered_by != caller {
+            return Err(ContractError::NotDocumentOwner);
+        }
+
+        let mut tags = load_tags(&env, &document_hash);
+        if tags.contains(&tag) {
+            return Ok(());
+        }
+        if tags.len() >= MAX_TAGS {
+            return Err(ContractError::TooManyTags);
+        }
+        tags.push_back(tag.clone());
+        env.storage()
+            .persistent()
+            .set(&(TAGS, document_hash.clone()), &tags);
+
+        let mut tag_index = load_tag_index(&env);
+        let mut hashes = tag_index.get(tag.clone()).unwrap_or(Vec::new(&env));
+        hashes.push_back(document_hash.clone());
+        tag_index.set(tag.clone(), hashes);
+        env.storage().persistent().set(&TAG_INDEX, &tag_index);
+
+        // Emit event
+        publish_event(
+            &env,
+            EventPriority::Detail,
+            symbol_short!("DOC_TAG"),
+            DocumentTaggedEvent {
+                document_hash,
+                tag,
+                added: true,
+                tagged_by: caller,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Remove a tag from a document owned by the caller. Removing a tag the
+    /// document does not carry does nothing.
+    pub fn remove_tag(
+        env: Env,
+        caller: Address,
+        document_hash: String,
+        tag: String,
+    ) -> Result<(), ContractError> {
+        // Require caller authorization
+        caller.require_auth();
+
+        check_not_paused(&env, OperationKind::Tag)?;
+
+        let document_hash = canonical_hash(&env, document_hash);
+        let record = load_documents(&env)
+            .get(document_hash.clone())
+            .ok_or(ContractError::DocumentNotFound)?;
+        if record.registered_by != caller {
+            return Err(ContractError::NotDocumentOwner);
+        }
+
+        let mut tags = load_tags(&env, &document_hash);
+        let Some(index) = tags.first_index_of(tag.clone()) else {
+            return Ok(());
+        };
+        tags.remove(index);
+        let tags_key = (TAGS, document_hash.clone());
+        if tags.is_empty() {
+            env.storage().persistent().remove(&tags_key);
+        } else {
+            env.storage().persistent().set(&tags_key, &tags);
+        }
+
+        let mut tag_index = load_tag_index(&env);
+        let mut hashes = tag_index.get(tag.clone()).unwrap_or(Vec::new(&env));
+        if let Some(index) = hashes.first_index_of(document_hash.clone()) {
+            hashes.remove(index);
+        }
+        if hashes.is_empty() {
+            tag_index.remove(tag.clone());
+        } else {
+            tag_index.set(tag.clone(), hashes);
+        }
+        env.storage().persistent().set(&TAG_INDEX, &tag_index);
+
+        // Emit event
+        publish_event(
+            &env,
+            EventPriority::Detail,
+            symbol_short!("DOC_TAG"),
+            DocumentTaggedEvent {
+                document_hash,
+                tag,
+                added: false,
+                tagged_by: caller,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Leave a note on a document as a verifier. Anyone may add notes; each
+    /// is at most `MAX_NOTE_LENGTH` characters and a document holds at most
+    /// `MAX_NOTES` of them. The owner can clear unwanted ones with
+    /// `remove_verifier_note`.
+    pub fn add_verifier_note(
+        env: Env,
+        verifier: Address,
+        document_hash: String,
+        note: String,
+    ) -> Result<(), ContractError> {
+        // Require verifier authorization
+        verifier.require_auth();
+
+        let document_hash = canonical_hash(&env, document_hash);
+        if note.is_empty() || note.len() > MAX_NOTE_LENGTH {
+            return Err(ContractError::InvalidNote);
+        }
+        if find_document(&env, &load_documents(&env), &document_hash).is_none() {
+            return Err(ContractError::DocumentNotFound);
+        }
+
+        let notes_key = (NOTES, document_hash);
+        let mut notes: Vec<VerifierNote> = env
+            .storage()
+            .persistent()
+            .get(&notes_key)
+            .unwrap_or(Vec::new(&env));
+        if notes.len() >= MAX_NOTES {
+            return Err(ContractError::TooManyNotes);
+        }
+        notes.push_back(VerifierNote {
+            author: verifier,
+            note,
+            timestamp: env.ledger().timestamp(),
+        });
+        env.storage().persistent().set(&notes_key, &notes);
+
+        Ok(())
+    }
+
+    /// Remove the note at `index` (as returned by `get_verifier_notes`) from a
+    /// document. The document owner can remove any note, other callers only
+    /// their own. An index with no note returns `InvalidNote`.
+    pub fn remove_verifier_note(
+        env: Env,
+        caller: Address,
+        document_hash: String,
+        index: u32,
+    ) -> Result<(), ContractError> {
+        // Require caller authorization
+        caller.require_auth();
+
+        let document_hash = canonical_hash(&env, document_hash);
+        let record = load_documents(&env)
+            .get(document_hash.clone())
+            .ok_or(ContractError::DocumentNotFound)?;
+
+        let notes_key = (NOTES, document_hash);
+        let mut notes: Vec<VerifierNote> = env
+            .storage()
+            .persistent()
+            .get(&notes_key)
+            .unwrap_or(Vec::new(&env));
+        let note = notes.get(index).ok_or(ContractError::InvalidNote)?;
+        if record.registered_by != caller && note.author != caller {
+            return Err(ContractError::NotDocumentOwner);
+        }
+        notes.remove(index);
+        env.storage().persistent().set(&notes_key, &notes);
+
+        Ok(())
+    }
+
+    /// Get the verifier notes on a document, oldest first. While user lists
+    /// are private, only the document owner or the admin can read them.
+    pub fn get_verifier_notes(
+        env: Env,
+        viewer: Option<Address>,
+        document_hash: String,
+    ) -> Result<Vec<VerifierNote>, ContractError> {
+        let document_hash = canonical_hash(&env, document_hash);
+        if let Some(record) = load_documents(&env).get(document_hash.clone()) {
+            require_list_access(&env, &viewer, Some(&record.registered_by))?;
+        }
+        Ok(env
+            .storage()
+            .persistent()
+            .get(&(NOTES, document_hash))
+            .unwrap_or(Vec::new(&env)))
+    }
+
+    /// Attach an issuer's seal to a document owned by the caller: an ed25519
+    /// signature by `issuer_key` over the UTF-8 bytes of the document hash.
+    /// Panics if the signature does not verify, so stored seals are always valid.
+    /// A seal cannot be replaced, and sealed documents cannot be deleted.
+    pub fn attach_seal(
+        env: Env,
+        caller: Address,
+        document_hash: String,
+        issuer_key: BytesN<32>,
+        seal: BytesN<64>,
+    ) -> Result<(), ContractError> {
+        // Require caller authorization
+        caller.require_auth();
+
+        check_not_paused(&env, OperationKind::Seal)?;
+
+        let document_hash = canonical_hash(&env, document_hash);
+        let mut documents = load_documents(&env);
+        let mut record = documents
+            .get(document_hash.clone())
+            .ok_or(ContractError::DocumentNotFound)?;
+        if record.registered_by != caller {
+            return Err(ContractError::NotDocumentOwner);
+        }
+        if record.seal.is_some() {
+            return Err(ContractError::DocumentSealed);
+        }
+
+        env.crypto()
+            .ed25519_verify(&issuer_key, &string_to_bytes(&env, &document_hash), &seal);
+
+        record.seal = Some(seal);
+        record.seal_key = Some(issuer_key);
+        documents.set(document_hash.clone(), record);
+        env.storage().persistent().set(&DOCUMENTS, &documents);
+
+        append_history(&env, &document_hash, HistoryAction::Sealed, &caller);
+
+        Ok(())
+    }
+
+    /// Check a document's issuer seal. Returns false for unknown documents and
+    /// documents without a seal.
+    pub fn verify_seal(env: Env, document_hash: String) -> bool {
+        let document_hash = canonical_hash(&env, document_hash);
+        let Some(record) = load_documents(&env).get(document_hash.clone()) else {
+            return false;
+        };
+        match (record.seal, record.seal_key) {
+            (Some(seal), Some(seal_key)) => {
+                env.crypto()
+                    .ed25519_verify(&seal_key, &string_to_bytes(&env, &document_hash), &seal);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Get the tags attached to a document
+    pub fn get_tags(env: Env, document_hash: String) -> Vec<String> {
+        let document_hash = canonical_hash(&env, document_hash);
+        load_tags(&env, &document_hash)
+    }
+
+    /// Get a page of the hashes of documents carrying `tag`, in tagging order.
+    /// The tag index spans every owner, so it is admin-only while user lists
+    /// are private.
+    pub fn get_documents_by_tag(
+        env: Env,
+        viewer: Option<Address>,
+        tag: String,
+        start: u32,
+        limit: u32,
+    ) -> Result<Vec<String>, ContractError> {
+        require_list_access(&env, &viewer, None)?;
+
+        let hashes = load_tag_index(&env).get(tag).unwrap_or(Vec::new(&env));
+        let end = start.saturating_add(limit.min(MAX_PAGE_SIZE)).min(hashes.len());
+
+        let mut result = Vec::new(&env);
+        for position in start..end {
+            if let Some(document_hash) = hashes.get(position) {
+                result.push_back(document_hash);
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Get the recorded lifecycle of a document, oldest entry first. The history
+    /// is kept after deletion and holds at most the latest 50 entries.
+    pub fn get_document_history(env: Env, document_hash: String) -> Vec<HistoryEntry> {
+        let document_hash = canonical_hash(&env, document_hash);
+        env.storage()
+            .persistent()
+            .get(&(HISTORY, document_hash))
+            .unwrap_or(Vec::new(&env))
+    }
+
+    /// Get a document's status as of a past ledger sequence by replaying its
+    /// history up to and including that block. Lock periods are not
+    /// reconstructed, so a document that existed at the block reads as
+    /// `Active`. If older entries were dropped from the history, the document
+    /// is taken to have existed before the oldest remaining entry.
+    pub fn status_at_block(env: Env, document_hash: String, block: u32) -> DocumentStatus {
+        let history = Self::get_document_history(env, document_hash);
+        // History that does not start with a registration was trimmed
+        let mut status = match history.first() {
+            Some(entry) if entry.action != HistoryAction::Registered => DocumentStatus::Active,
+            _ => DocumentStatus::NotFound,
+        };
+        for entry in history.iter() {
+            if entry.block_number > block {
+                break;
+            }
+            status = match entry.action {
+                HistoryAction::Deleted => DocumentStatus::Deleted,
+                _ => DocumentStatus::Active,
+            };
+        }
+        status
+    }
+
+    /// Look up a document by the external reference ID it was registered with
+    pub fn get_document_by_external_ref(env: Env, external_ref: String) -> DocumentInfo {
+        match load_ref_index(&env).get(external_ref) {
+            Some(document_hash) => Self::verify_document(env, document_hash),
+            None => document_info(&env, None),
+        }
+    }
+
+    /// Check a name against the one a document was registered with. The stored
+    /// name hash is fixed at registration, so it keeps proving the original
+    /// name even if the document's name is later changed.
+    pub fn verify_name(env: Env, document_hash: String, name: String) -> bool {
+        let document_hash = canonical_hash(&env, document_hash);
+        match load_documents(&env).get(document_hash) {
+            Some(record) => name.len() as usize <= MAX_STRING_BYTES
+                && hash_name(&env, &name) == record.name_hash,
+            None => false,
+        }
+    }
+
+    /// Compute a fingerprint of a hash and name pair for cross-system
+    /// deduplication. It is the SHA-256 digest of the hash's 64 UTF-8 bytes
+    /// immediately followed by the name's UTF-8 bytes, with no separator or
+    /// length prefix. Nothing is read from storage.
+    pub fn compute_fingerprint(
+        env: Env,
+        document_hash: String,
+        document_name: String,
+    ) -> Result<BytesN<32>, ContractError> {
+        let document_hash = canonical_hash(&env, document_hash);
+        if document_hash.len() != HASH_LENGTH {
+            return Err(ContractError::InvalidHashLength);
+        }
+        if document_name.len() > MAX_NAME_LENGTH {
+            return Err(ContractError::InvalidDocumentName);
+        }
+
+        let mut preimage = string_to_bytes(&env, &document_hash);
+        preimage.append(&string_to_bytes(&env, &document_name));
+        Ok(env.crypto().sha256(&preimage).into())
+    }
+
+    /// Whether a document exists and was registered at or before `deadline_ts`
+    pub fn was_registered_before(env: Env, document_hash: String, deadline_ts: u64) -> bool {
+        let document_hash = canonical_hash(&env, document_hash);
+        find_document(&env, &load_documents(&env), &document_hash)
+            .is_some_and(|record| record.timestamp <= deadline_ts)
+    }
+
+    /// Whether a document exists and was registered no later than `timestamp`.
+    /// Same check as `was_registered_before`, named for prior-existence proofs.
+    pub fn existed_before(env: Env, document_hash: String, timestamp: u64) -> bool {
+        Self::was_registered_before(env, document_hash, timestamp)
+    }
+
+    /// Return the hashes from `hashes` that are not registered. Malformed hashes
+    /// can never be registered, so they are always reported as missing. Hashes are
+    /// reported as passed in, with any `0x` prefix kept.
+    pub fn find_missing_hashes(env: Env, hashes: Vec<String>) -> Result<Vec<String>, ContractError> {
+        check_batch_size(&env, hashes.len())?;
+
+        let documents = load_documents(&env);
+        let mut missing = Vec::new(&env);
+        for document_hash in hashes.iter() {
+            let canonical = canonical_hash(&env, document_hash.clone());
+            if find_document(&env, &documents, &canonical).is_none() {
+                missing.push_back(document_hash);
+            }
+        }
+
+        Ok(missing)
+    }
+
+    /// Check that every hash in `hashes` is registered and owned by `owner`.
+    /// Returns false if any is missing or owned by someone else, and for an
+    /// empty list.
+    pub fn all_owned_by(env: Env, hashes: Vec<String>, owner: Address) -> Result<bool, ContractError> {
+        check_batch_size(&env, hashes.len())?;
+
+        if hashes.is_empty() {
+            return Ok(false);
+        }
+        let documents = load_documents(&env);
+        for document_hash in hashes.iter() {
+            match find_document(&env, &documents, &canonical_hash(&env, document_hash)) {
+                Some(record) if record.registered_by == owner => {}
+                _ => return Ok(false),
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Check that every hash in `hashes` was registered by `owner`. A missing
+    /// hash counts as not registered by `owner`, so it makes the result false.
+    /// Same as `all_owned_by`.
+    pub fn all_registered_by(env: Env, hashes: Vec<String>, owner: Address) -> Result<bool, ContractError> {
+        Self::all_owned_by(env, hashes, owner)
+    }
+
+    /// Issue a fresh challenge to the owner of a document, to be answered with
+    /// `verify_proof`. It replaces any earlier challenge for the same caller and
+    /// document, expires after `PROOF_CHALLENGE_TTL` seconds and is dropped when
+    /// the document is transferred, rekeyed or deleted.
+    pub fn request_proof(
+        env: Env,
+        caller: Address,
+        document_hash: String,
+    ) -> Result<ProofChallenge, ContractError> {
+        // Require caller authorization
+        caller.require_auth();
+
+        let document_hash = canonical_hash(&env, document_hash);
+        let record = load_documents(&env)
+            .get(document_hash.clone())
+            .ok_or(ContractError::DocumentNotFound)?;
+        if record.registered_by != caller {
+            return Err(ContractError::NotDocumentOwner);
+        }
+
+        let nonce: u64 = env.storage().instance().get(&CHALLENGE_NONCE).unwrap_or(0);
+        env.storage().instance().set(&CHALLENGE_NONCE, &(nonce + 1));
+
+        let challenge = ProofChallenge {
+            document_hash: document_hash.clone(),
+            owner: caller.clone(),
+            nonce,
+            expires_at: env.ledger().timestamp().saturating_add(PROOF_CHALLENGE_TTL),
+        };
+        env.storage()
+            .persistent()
+            .set(&(PROOF_CHALLENGES, caller, document_hash), &challenge);
+
+        Ok(challenge)
+    }
+
+    /// Get the latest challenge issued to `caller` for a document, if any. It
+    /// may have expired.
+    pub fn get_proof_challenge(env: Env, caller: Address, document_hash: String) -> Option<ProofChallenge> {
+        let document_hash = canonical_hash(&env, document_hash);
+        env.storage()
+            .persistent()
+            .get(&(PROOF_CHALLENGES, caller, document_hash))
+    }
+
+    /// Answer a challenge from `request_proof`. The caller's authorization of
+    /// this call, which covers `nonce`, is the signature. The challenge is used
+    /// up on success, so each one proves ownership at most once.
+    pub fn verify_proof(env: Env, caller: Address, document_hash: String, nonce: u64) -> Result<(), ContractError> {
+        // Require caller authorization
+        caller.require_auth();
+
+        let document_hash = canonical_hash(&env, document_hash);
+        let challenge_key = (PROOF_CHALLENGES, caller.clone(), document_hash.clone());
+        let challenge: ProofChallenge = env
+            .storage()
+            .persistent()
+            .get(&challenge_key)
+            .ok_or(ContractError::ProofChallengeInvalid)?;
+        if challenge.nonce != nonce {
+            return Err(ContractError::ProofChallengeInvalid);
+        }
+        if env.ledger().timestamp() > challenge.expires_at {
+            return Err(ContractError::ProofChallengeExpired);
+        }
+        let record = load_documents(&env)
+            .get(document_hash)
+            .ok_or(ContractError::DocumentNotFound)?;
+        if record.registered_by != caller {
+            return Err(ContractError::NotDocumentOwner);
+        }
+
+        env.storage().persistent().remove(&challenge_key);
+
+        Ok(())
+    }
+
+    /// Check whether any document was registered in a ledger. Registrations
+    /// stay counted after the document is deleted or rekeyed. Documents
+    /// imported by the admin are not counted in any ledger.
+    pub fn has_documents_in_block(env: Env, block_number: u32) -> bool {
+        env.storage()
+            .persistent()
+            .has(&(BLOCK_REGISTRATIONS, block_number))
+    }
+
+    /// Return the ledgers from `from` to `to` (inclusive) in which no document
+    /// was registered. At most `limit` ledgers (up to `MAX_PAGE_SIZE`) are
+    /// scanned, so continue from `from + limit` to cover a longer range.
+    pub fn find_empty_blocks(env: Env, from: u32, to: u32, limit: u32) -> Vec<u32> {
+        let mut empty = Vec::new(&env);
+        if from > to || limit == 0 {
+            return empty;
+        }
+        let end = to.min(from.saturating_add(limit.min(MAX_PAGE_SIZE) - 1));
+
+        for block_number in from..=end {
+            if !Self::has_documents_in_block(env.clone(), block_number) {
+                empty.push_back(block_number);
+            }
+        }
+
+        empty
+    }
+
+    /// Get the zero-based position of a document in the global registration
+    /// order. Positions are never reused, so they become sparse as documents are
+    /// deleted; a rekeyed document keeps its position. Documents registered
+    /// before the order was recorded have none.
+    pub fn get_document_order_index(env: Env, document_hash: String) -> Option<u32> {
+        let document_hash = canonical_hash(&env, document_hash);
+        env.storage()
+            .persistent()
+            .get(&(ORDER_POSITIONS, document_hash))
+    }
+
+    /// Compare two registered documents
+    pub fn compare_documents(env: Env, hash_a: String, hash_b: String) -> Result<DocumentDiff, ContractError> {
+        let hash_a = canonical_hash(&env, hash_a);
+        let hash_b = canonical_hash(&env, hash_b);
+        let documents = load_documents(&env);
+        let record_a = documents.get(hash_a).ok_or(ContractError::DocumentNotFound)?;
+        let record_b = documents.get(hash_b).ok_or(ContractError::DocumentNotFound)?;
+
+        Ok(DocumentDiff {
+            same_name: record_a.document_name == record_b.document_name,
+            same_owner: record_a.registered_by == record_b.registered_by,
+            time_delta: record_b.timestamp as i64 - record_a.timestamp as i64,
+        })
+    }
+
+    /// Get the documents with `status` among the order index positions
+    /// `start` to `start + limit` (at most `MAX_PAGE_SIZE` positions), in
+    /// registration order. Continue from `start + limit` for the next page.
+    /// Deleted documents leave the index, so asking for `Deleted` matches
+    /// nothing; use `get_tombstone` for those.
+    pub fn get_documents_by_status(
+        env: Env,
+        viewer: Option<Address>,
+        status: DocumentStatus,
+        start: u32,
+        limit: u32,
+    ) -> Result<Vec<DocumentRecord>, ContractError> {
+        require_list_access(&env, &viewer, None)?;
+
+        let next: u32 = env.storage().instance().get(&NEXT_ORDER).unwrap_or(0);
+        let end = start.saturating_add(limit.min(MAX_PAGE_SIZE)).min(next);
+
+        let documents = load_documents(&env);
+        let now = env.ledger().timestamp();
+        let mut result = Vec::new(&env);
+        for position in start..end {
+            let document_hash: Option<String> = env.storage().persistent().get(&(ORDER_HASHES, position));
+            if let Some(record) = document_hash.and_then(|document_hash| documents.get(document_hash))
+                && compute_status(&record, now) == status
+            {
+                result.push_back(record);
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Get the registration journal entries `start` to `start + limit` (at most
+    /// `MAX_PAGE_SIZE`) as (journal seq, hash, owner), oldest first. Every
+    /// permanent registration appends one entry and deletions leave it in
+    /// place, so an indexer can rebuild the registration history from here.
+    /// Each entry is its own persistent key, so storage grows by one entry per
+    /// registration and old entries expire with the usual persistent TTL
+    /// unless extended.
+    pub fn get_journal(
+        env: Env,
+        viewer: Option<Address>,
+        start: u64,
+        limit: u32,
+    ) -> Result<Vec<(u64, String, Address)>, ContractError> {
+        require_list_access(&env, &viewer, None)?;
+
+        let length: u64 = env.storage().instance().get(&JOURNAL_LENGTH).unwrap_or(0);
+        let end = start
+            .saturating_add(limit.min(MAX_PAGE_SIZE) as u64)
+            .min(length);
+
+        let mut result = Vec::new(&env);
+        for journal_seq in start..end {
+            if let Some(entry) = env.storage().persistent().get(&(JOURNAL, journal_seq)) {
+                result.push_back(entry);
+            }
+        }
+        Ok(result)
+    }
+
+    /// Get the registration order position a document was given, starting at 1
+    pub fn get_document_seq(env: Env, document_hash: String) -> Option<u64> {
+        let document_hash = canonical_hash(&env, document_hash);
+        load_documents(&env)
+            .get(document_hash)
+            .map(|record| record.seq)
+    }
+
+    /// Count the documents registered within the last `within_ledgers` ledgers.
+    /// The registration order index is scanned from its newest position
+    /// backwards and stops at the first document registered before the window,
+    /// so the cost grows with the number of recent registrations (plus any
+    /// deleted positions among them) rather than with the registry size.
+    /// Imported documents carry their original block and never stop the scan.
+    pub fn count_recent(env: Env, within_ledgers: u32) -> u32 {
+        let threshold = env.ledger().sequence().saturating_sub(within_ledgers);
+        let mut position: u32 = env.storage().instance().get(&NEXT_ORDER).unwrap_or(0);
+
+        let documents = load_documents(&env);
+        let mut count = 0u32;
+        while position > 0 {
+            position -= 1;
+            let document_hash: Option<String> = env.storage().persistent().get(&(ORDER_HASHES, position));
+            let Some(record) = document_hash.and_then(|document_hash| documents.get(document_hash)) else {
+                continue;
+            };
+            if record.block_number >= threshold {
+                count += 1;
+            } else if !record.imported {
+                break;
+            }
+        }
+
+        count
+    }
+
+    /// Get how many ledgers have closed since a document was registered
+    pub fn get_document_age(env: Env, document_hash: String) -> Option<u32> {
+        let document_hash = canonical_hash(&env, document_hash);
+        let record = find_document(&env, &load_documents(&env), &document_hash)?;
+        Some(env.ledger().sequence().saturating_sub(record.block_number))
+    }
+
+    /// Get all documents registered by a user. While user lists are private,
+    /// this requires the user's authorization.
+    pub fn get_user_documents(env: Env, user: Address) -> Vec<DocumentRecord> {
+        require_user_auth_if_private(&env, &user);
+
+        load_user_records(&env, &user)
+    }
+
+    /// Export all of a user's documents together with the current ledger and
+    /// a SHA-256 digest over the XDR-serialized record list, so the snapshot's
+    /// integrity can be checked off-chain. For large users the same records
+    /// can be fetched in pages with `get_user_documents_cursor` and checked
+    /// against the digest once reassembled.
+    pub fn export_user_snapshot(
+        env: Env,
+        viewer: Option<Address>,
+        user: Address,
+    ) -> Result<UserSnapshot, ContractError> {
+        require_list_access(&env, &viewer, Some(&user))?;
+
+        let documents = load_user_records(&env, &user);
+        let digest = env.crypto().sha256(&documents.clone().to_xdr(&env)).into();
+        Ok(UserSnapshot {
+            owner: user,
+            documents,
+            ledger_sequence: env.ledger().sequence(),
+            timestamp: env.ledger().timestamp(),
+            digest,
+        })
+    }
+
+    /// Get a page of at most `limit` of a user's documents (1 to
+    /// `MAX_PAGE_SIZE`, clamped), in `seq` order, starting after the document
+    /// with sequence number `after_seq`, or at the beginning when it is `None`.
+    /// Returns the records and the cursor for the next page, the `seq` of the
+    /// last record returned, which is `None` once the list is exhausted.
+    /// Sequence numbers are never reused, so the cursor stays valid when that
+    /// document is deleted or transferred, and other changes to the list do
+    /// not shift the page.
+    pub fn get_user_documents_cursor(
+        env: Env,
+        viewer: Option<Address>,
+        user: Address,
+        after_seq: Option<u64>,
+        limit: u32,
+    ) -> Result<(Vec<DocumentRecord>, Option<u64>), ContractError> {
+        require_list_access(&env, &viewer, Some(&user))?;
+
+        let user_docs = load_user_docs(&env, &user);
+        let documents = load_documents(&env);
+        let start = match after_seq {
+            Some(after_seq) => seq_position(&documents, &user_docs, after_seq.saturating_add(1)),
+            None => 0,
+        };
+        let end = start.saturating_add(limit.clamp(1, MAX_PAGE_SIZE)).min(user_docs.len());
+
+        let mut result = Vec::new(&env);
+        for position in start..end {
+            if let Some(record) = user_docs
+                .get(position)
+                .and_then(|document_hash| documents.get(document_hash))
+            {
+                result.push_back(record);
+            }
+        }
+
+        let next_cursor = match result.last() {
+            Some(record) if end < user_docs.len() => Some(record.seq),
+            _ => None,
+        };
+
+        Ok((result, next_cursor))
+    }
+
+    /// Return up to `max_items` hashes in a user's list that have no stored
+    /// record, in list order
+    pub fn find_orphaned_user_docs(env: Env, user: Address, max_items: u32) -> Vec<String> {
+        let documents = load_documents(&env);
+        let mut orphaned = Vec::new(&env);
+        for document_hash in load_user_docs(&env, &user).iter() {
+            if orphaned.len() >= max_items {
+                break;
+            }
+            if !documents.contains_key(document_hash.clone()) {
+                orphaned.push_back(document_hash);
+            }
+        }
+
+        orphaned
+    }
+
+    /// Remove up to `max_items` orphaned hashes from a user's list, returning
+    /// how many were removed. The caller must be the user or the admin.
+    pub fn cleanup_orphaned_user_docs(
+        env: Env,
+        caller: Address,
+        user: Address,
+        max_items: u32,
+    ) -> Result<u32, ContractError> {
+        if caller == user {
+            caller.require_auth();
+        } else {
+            require_admin(&env, &caller)?;
+        }
+
+        let orphaned = Self::find_orphaned_user_docs(env.clone(), user.clone(), max_items);
+        if orphaned.is_empty() {
+            return Ok(0);
+        }
+
+        let mut user_docs = load_user_docs(&env, &user);
+        for document_hash in orphaned.iter() {
+            if let Some(index) = user_docs.first_index_of(document_hash) {
+                user_docs.remove(index);
+            }
+        }
+        env.storage()
+            .persistent()
+            .set(&(USER_DOCS, user), &user_docs);
+
+        Ok(orphaned.len())
+    }
+
+    /// Check whether a user currently owns any documents, without loading records
+    pub fn user_has_documents(env: Env, user: Address) -> bool {
+        !load_user_docs(&env, &user).is_empty()
+    }
+
+    /// Get a page of the caller's own documents in the order of their list.
+    /// Requires the caller's authorization so results always belong to the
+    /// authenticated account. At most `limit` (up to `MAX_PAGE_SIZE`) are returned.
+    pub fn get_my_documents(env: Env, caller: Address, start: u32, limit: u32) -> Vec<DocumentRecord> {
+        caller.require_auth();
+
+        let user_docs = load_user_docs(&env, &caller);
+        let end = start.saturating_add(limit.min(MAX_PAGE_SIZE)).min(user_docs.len());
+
+        let documents = load_documents(&env);
+        let mut result = Vec::new(&env);
+        for position in start..end {
+            if let Some(record) = user_docs
+                .get(position)
+                .and_then(|document_hash| documents.get(document_hash))
+            {
+                result.push_back(record);
+            }
+        }
+
+        result
+    }
+
+    /// Get a page of a user's documents registered between `from_ts` and
+    /// `to_ts` (inclusive), in the order of the user's list. `start` counts
+    /// matching records to skip and at most `limit` (up to `MAX_PAGE_SIZE`)
+    /// are returned.
+    pub fn get_user_documents_in_range(
+        env: Env,
+        viewer: Option<Address>,
+        user: Address,
+        from_ts: u64,
+        to_ts: u64,
+        start: u32,
+        limit: u32,
+    ) -> Result<Vec<DocumentRecord>, ContractError> {
+        require_list_access(&env, &viewer, Some(&user))?;
+
+        if from_ts > to_ts {
+            return Err(ContractError::InvalidTimeRange);
+        }
+
+        let documents = load_documents(&env);
+        let limit = limit.min(MAX_PAGE_SIZE);
+        let mut skipped = 0u32;
+        let mut result = Vec::new(&env);
+        for hash in load_user_docs(&env, &user).iter() {
+            if result.len() >= limit {
+                break;
+            }
+            let Some(record) = documents.get(hash) else {
+                continue;
+            };
+            if record.timestamp < from_ts || record.timestamp > to_ts {
+                continue;
+            }
+            if skipped < start {
+                skipped += 1;
+                continue;
+            }
+            result.push_back(record);
+        }
+
+        Ok(result)
+    }
+
+    /// Get a page of the documents matching every condition set in `filter`.
+    /// With an owner the owner's list is scanned in order, otherwise all
+    /// documents are scanned in hash order. `start` counts matching records to
+    /// skip and at most `limit` (up to `MAX_PAGE_SIZE`) are returned.
+    pub fn query_documents(
+        env: Env,
+        viewer: Option<Address>,
+        filter: DocumentFilter,
+        start: u32,
+        limit: u32,
+    ) -> Result<Vec<DocumentRecord>, ContractError> {
+        require_list_access(&env, &viewer, filter.owner.as_ref())?;
+
+        let documents = load_documents(&env);
+        let hashes = match &filter.owner {
+            Some(owner) => load_user_docs(&env, owner),
+            None => documents.keys(),
+        };
+
+        let now = env.ledger().timestamp();
+        let limit = limit.min(MAX_PAGE_SIZE);
+        let mut skipped = 0u32;
+        let mut result = Vec::new(&env);
+        for hash in hashes.iter() {
+            if result.len() >= limit {
+                break;
+            }
+            let Some(record) = documents.get(hash) else {
+                continue;
+            };
+            if filter.from_ts.is_some_and(|from_ts| record.timestamp < from_ts)
+                || filter.to_ts.is_some_and(|to_ts| record.timestamp > to_ts)
+                || (!filter.statuses.is_empty() && !filter.statuses.contains(compute_status(&record, now)))
+            {
+                continue;
+            }
+            if skipped < start {
+                skipped += 1;
+                continue;
+            }
+            result.push_back(record);
+        }
+
+        Ok(result)
+    }
+
+    /// Export a user's document hashes as raw bytes, 32 bytes per hash in the
+    /// order of the user's list. Hashes that are not valid hex are skipped, so
+    /// the result can always be split into 32-byte chunks.
+    pub fn export_user_hashes(env: Env, viewer: Option<Address>, user: Address) -> Result<Bytes, ContractError> {
+        require_list_access(&env, &viewer, Some(&user))?;
+
+        let mut result = Bytes::new(&env);
+        for document_hash in load_user_docs(&env, &user).iter() {
+            if let Some(raw) = decode_hash(&document_hash) {
+                result.extend_from_slice(&raw);
+            }
+        }
+        Ok(result)
+    }
+
+    /// Get total number of registered documents
+    pub fn get_document_count(env: Env) -> u64 {
+        load_document_count(&env)
+    }
+
+    /// Get the earliest and latest registration timestamps, or `(0, 0)` when
+    /// no documents are registered. Deleting documents does not narrow the span.
+    pub fn get_registry_span(env: Env) -> (u64, u64) {
+        if Self::get_document_count(env.clone()) == 0 {
+            return (0, 0);
+        }
+        let storage = env.storage().instance();
+        (
+            storage.get(&FIRST_TIMESTAMP).unwrap_or(0),
+            storage.get(&LAST_TIMESTAMP).unwrap_or(0),
+        )
+    }
+
+    /// Estimate the storage used by a user's documents in bytes. This sums the
+    /// XDR-serialized size of each record and of the user's hash list, and
+    /// ignores ledger entry overhead and per-document side data.
+    pub fn estimate_user_storage(env: Env, user: Address) -> u32 {
+        let user_doc_hashes: Vec<String> = env
+            .storage()
+            .persistent()
+            .get(&(USER_DOCS, user))
+            .unwrap_or(Vec::new(&env));
+        if user_doc_hashes.is_empty() {
+            return 0;
+        }
+
+        let documents = load_documents(&env);
+        let mut total = user_doc_hashes.clone().to_xdr(&env).len();
+        for hash in user_doc_hashes.iter() {
+            if let Some(record) = documents.get(hash) {
+                total = total.saturating_add(record.to_xdr(&env).len());
+            }
+        }
+
+        total
+    }
+
+    /// Estimate the bytes used by every document record, for budgeting rent.
+    /// This is an approximation: it measures the XDR-serialized size of the
+    /// first `sample` records (at most `MAX_PAGE_SIZE`) and scales their
+    /// average by the document count. Ledger entry overhead and per-document
+    /// side data are not included.
+    pub fn estimate_storage(env: Env, sample: u32) -> u64 {
+        let count = Self::get_document_count(env.clone());
+        let sample = sample.min(MAX_PAGE_SIZE);
+        if count == 0 || sample == 0 {
+            return 0;
+        }
+
+        let documents = load_documents(&env);
+        let mut sampled = 0u64;
+        let mut sampled_bytes = 0u64;
+        for record in documents.values().iter().take(sample as usize) {
+            sampled += 1;
+            sampled_bytes = sampled_bytes.saturating_add(record.to_xdr(&env).len() as u64);
+        }
+        if sampled == 0 {
+            return 0;
+        }
+
+        sampled_bytes.saturating_mul(count) / sampled
+    }
+
+    /// Get the number of distinct addresses that have ever registered a document.
+    /// Deleting documents never lowers it, even when a user has none left.
+    pub fn get_unique_user_count(env: Env) -> u32 {
+        env.storage().instance().get(&UNIQUE_USERS).unwrap_or(0)
+    }
+
+    /// Get a page of every address that has ever registered a document, in the
+    /// order they first registered. `limit` is capped at 100. Admin-only while
+    /// user lists are private.
+    pub fn get_all_registrants(
+        env: Env,
+        viewer: Option<Address>,
+        start: u32,
+        limit: u32,
+    ) -> Result<Vec<Address>, ContractError> {
+        require_list_access(&env, &viewer, None)?;
+
+        let total = Self::get_unique_user_count(env.clone());
+        let end = start.saturating_add(limit.min(MAX_PAGE_SIZE)).min(total);
+
+        let mut result = Vec::new(&env);
+        for position in start..end {
+            if let Some(address) = env.storage().persistent().get(&(REGISTRANTS, position)) {
+                result.push_back(address);
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Rebuild the name counts and external reference index from the stored
+    /// records, `limit` documents at a time (admin only). Start with `start = 0`,
+    /// then pass the returned `processed` value until `done`. The new indexes
+    /// are built aside and only replace the live ones on the last page.
+    /// Registration, deletion and rekeying must be paused for the whole pass,
+    /// otherwise this returns `RegistryNotPaused`, since they would shift the
+    /// documents between pages.
+    pub fn rebuild_indexes(
+        env: Env,
+        caller: Address,
+        start: u32,
+        limit: u32,
+    ) -> Result<RebuildProgress, ContractError> {
+        require_admin(&env, &caller)?;
+
+        for operation in [OperationKind::Register, OperationKind::Delete, OperationKind::Rekey] {
+            if !Self::is_operation_paused(env.clone(), operation) {
+                return Err(ContractError::RegistryNotPaused);
+            }
+        }
+
+        let (mut name_counts, mut ref_index): (Map<String, u32>, Map<String, String>) = if start == 0 {
+            (Map::new(&env), Map::new(&env))
+        } else {
+            let cursor: u32 = env
+                .storage()
+                .instance()
+                .get(&REBUILD_CURSOR)
+                .ok_or(ContractError::InvalidCursor)?;
+            if cursor != start {
+                return Err(ContractError::InvalidCursor);
+            }
+            let storage = env.storage().persistent();
+            (
+                storage.get(&REBUILD_NAME_COUNTS).unwrap_or(Map::new(&env)),
+                storage.get(&REBUILD_REF_INDEX).unwrap_or(Map::new(&env)),
+            )
+        };
+
+        let documents = load_documents(&env);
+        let total = documents.len();
+        let end = start.saturating_add(limit.min(MAX_PAGE_SIZE)).min(total);
+        for (document_hash, record) in documents
+            .iter()
+            .skip(start as usize)
+            .take(end.saturating_sub(start) as usize)
+        {
+            let uses = name_counts.get(record.document_name.clone()).unwrap_or(0);
+            name_counts.set(record.document_name, uses + 1);
+            if let Some(external_ref) = record.external_ref {
+                ref_index.set(external_ref, document_hash);
+            }
+        }
+
+        let done = end >= total;
+        if done {
+            env.storage().persistent().set(&NAME_COUNTS, &name_counts);
+            env.storage().persistent().set(&REF_INDEX, &ref_index);
+            env.storage().persistent().remove(&REBUILD_NAME_COUNTS);
+            env.storage().persistent().remove(&REBUILD_REF_INDEX);
+            env.storage().instance().remove(&REBUILD_CURSOR);
+            publish_event(
+                &env,
+                EventPriority::Detail,
+                symbol_short!("IDX_DONE"),
+                IndexesRebuiltEvent {
+                    total,
+                    rebuilt_by: caller,
+                },
+            );
+        } else {
+            env.storage()
+                .persistent()
+                .set(&REBUILD_NAME_COUNTS, &name_counts);
+            env.storage()
+                .persistent()
+                .set(&REBUILD_REF_INDEX, &ref_index);
+            env.storage().instance().set(&REBUILD_CURSOR, &end);
+        }
+
+        Ok(RebuildProgress {
+            processed: end.max(start),
+            total,
+            done,
+        })
+    }
+
+    /// Export a page of all document records for off-chain backup (admin only).
+    /// Records are ordered by hash; `limit` is capped at 100.
+    pub fn export_snapshot(
+        env: Env,
+        admin: Address,
+        start: u32,
+        limit: u32,
+    ) -> Result<Vec<DocumentRecord>, ContractError> {
+        require_admin(&env, &admin)?;
+
+        let mut result = Vec::new(&env);
+        for (_, record) in load_documents(&env)
+            .iter()
+            .skip(start as usize)
+            .take(limit.min(MAX_PAGE_SIZE) as usize)
+        {
+            result.push_back(record);
+        }
+
+        Ok(result)
+    }
+
+    /// Compute a digest summarizing the first `max_items` documents. When the
+    /// registry holds at most `max_items` documents this is the full state root.
+    pub fn compute_state_root(env: Env, max_items: u32) -> BytesN<32> {
+        Self::accumulate_state_root(env.clone(), BytesN::from_array(&env, &[0u8; 32]), 0, max_items)
+            .accumulator
+    }
+
+    /// Fold a page of document hashes into a state root accumulator.
+    ///
+    /// Starting from 32 zero bytes at index 0, each document in DOCUMENTS key
+    /// order (ascending hash) updates the accumulator to
+    /// `sha256(accumulator || hash)`, where `hash` is the UTF-8 bytes of the
+    /// 64-character hash string. Feed `accumulator` and `next` back in until
+    /// `done`. The result is deterministic for a given document set and
+    /// changes whenever a document is registered, deleted or rekeyed.
+    pub fn accumulate_state_root(
+        env: Env,
+        accumulator: BytesN<32>,
+        start: u32,
+        limit: u32,
+    ) -> StateRootProgress {
+        let documents = load_documents(&env);
+        let total = documents.len();
+        let mut accumulator = accumulator;
+        let mut next = start;
+
+        for document_hash in documents.keys().iter().skip(start as usize).take(limit as usize) {
+            let mut input = Bytes::from_array(&env, &accumulator.to_array());
+            input.append(&string_to_bytes(&env, &document_hash));
+            accumulator = env.crypto().sha256(&input).into();
+            next += 1;
+        }
+
+        StateRootProgress {
+            accumulator,
+            next,
+            done: next >= total,
+        }
+    }
+
+    /// Get aggregate registry statistics
+    pub fn get_contract_stats(env: Env) -> ContractStats {
+        ContractStats {
+            total_documents: Self::get_document_count(env.clone()),
+            total_users: Self::get_unique_user_count(env),
+        }
+    }
+
+    /// Check if a document name is already used by a user. While user lists are
+    /// private, this requires the user's authorization.
+    pub fn is_document_name_used(env: Env, user: Address, document_name: String) -> bool {
+        require_user_auth_if_private(&env, &user);
+
+        let documents = load_documents(&env);
+        for hash in load_user_docs(&env, &user).iter() {
+            if documents
+                .get(hash)
+                .is_some_and(|doc| doc.document_name == document_name)
+            {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Get document by name for a specific user. While user lists are private,
+    /// this requires the user's authorization.
+    pub fn get_document_by_name(env: Env, user: Address, document_name: String) -> DocumentInfo {
+        require_user_auth_if_private(&env, &user);
+
+        // Walk the user's hashes and stop at the first record with the name.
+        // Every record lives in the one documents entry, which is only read
+        // when the user has any documents at all.
+        let user_docs = load_user_docs(&env, &user);
+        if user_docs.is_empty() {
+            return document_info(&env, None);
+        }
+        let documents = load_documents(&env);
+        for hash in user_docs.iter() {
+            if let Some(doc) = documents.get(hash)
+                && doc.document_name == document_name
+            {
+                return document_info(&env, Some(doc));
+            }
+        }
+
+        document_info(&env, None)
+    }
+}
+
+#[cfg(test)]
+mod test;
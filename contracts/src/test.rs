@@ -0,0 +1,500 @@
+extern crate std;
+
+use super::*;
+use ed25519_dalek::{Signer, SigningKey};
+use soroban_sdk::testutils::{Address as _, Ledger};
+
+fn hash(env: &Env, c: char) -> String {
+    let hex: std::string::String = core::iter::repeat_n(c, 64).collect();
+    String::from_str(env, &hex)
+}
+
+fn name(env: &Env, value: &str) -> String {
+    String::from_str(env, value)
+}
+
+fn setup(env: &Env) -> (DocumentVerificationContractClient<'_>, Address) {
+    env.mock_all_auths();
+    let contract_id = env.register(DocumentVerificationContract, ());
+    let client = DocumentVerificationContractClient::new(env, &contract_id);
+    let admin = Address::generate(env);
+    client.initialize(&admin);
+    (client, admin)
+}
+
+fn locked_until(revocable_after: u64) -> RegistrationOptions {
+    RegistrationOptions {
+        encrypted: false,
+        key_ref: None,
+        external_ref: None,
+        revocable_after: Some(revocable_after),
+    }
+}
+
+#[test]
+fn compute_status_locks_until_revocable_after() {
+    let env = Env::default();
+    env.ledger().set_timestamp(1000);
+    let (client, _admin) = setup(&env);
+    let user = Address::generate(&env);
+    client.register_document_with_options(&user, &hash(&env, 'a'), &name(&env, "A"), &locked_until(2000));
+    let mut record = client.verify_document(&hash(&env, 'a')).record.first().unwrap();
+
+    assert_eq!(compute_status(&record, 1999), DocumentStatus::Locked);
+    assert_eq!(compute_status(&record, 2000), DocumentStatus::Active);
+    assert_eq!(compute_status(&record, 3000), DocumentStatus::Active);
+    record.revocable_after = None;
+    assert_eq!(compute_status(&record, 0), DocumentStatus::Active);
+}
+
+#[test]
+fn verify_document_reports_lock_then_active() {
+    let env = Env::default();
+    env.ledger().set_timestamp(1000);
+    let (client, _admin) = setup(&env);
+    let user = Address::generate(&env);
+    client.register_document_with_options(&user, &hash(&env, 'a'), &name(&env, "A"), &locked_until(2000));
+
+    assert_eq!(client.verify_document(&hash(&env, 'a')).status, DocumentStatus::Locked);
+    env.ledger().set_timestamp(2000);
+    assert_eq!(client.verify_document(&hash(&env, 'a')).status, DocumentStatus::Active);
+}
+
+#[test]
+fn verify_document_reports_not_found_then_deleted() {
+    let env = Env::default();
+    let (client, _admin) = setup(&env);
+    let user = Address::generate(&env);
+
+    assert_eq!(client.verify_document(&hash(&env, 'a')).status, DocumentStatus::NotFound);
+    client.register_document(&user, &hash(&env, 'a'), &name(&env, "A"));
+    client.delete_document(&user, &hash(&env, 'a'));
+
+    let info = client.verify_document(&hash(&env, 'a'));
+    assert!(!info.exists);
+    assert_eq!(info.status, DocumentStatus::Deleted);
+    assert_eq!(info.tombstone.first().unwrap().deleted_by, user);
+}
+
+#[test]
+fn prefixed_register_verifies_with_bare_hash() {
+    let env = Env::default();
+    let (client, _admin) = setup(&env);
+    let user = Address::generate(&env);
+    let bare = "ab".repeat(32);
+    client.register_document(&user, &name(&env, &std::format!("0x{bare}")), &name(&env, "A"));
+
+    let info = client.verify_document(&name(&env, &bare));
+    assert!(info.exists);
+    assert_eq!(info.record.first().unwrap().document_hash, name(&env, &bare));
+    assert!(client.verify_document(&name(&env, &std::format!("0X{bare}"))).exists);
+    assert_eq!(
+        client.try_register_document(&user, &name(&env, &bare), &name(&env, "B")),
+        Err(Ok(ContractError::DocumentAlreadyExists))
+    );
+}
+
+#[test]
+fn is_document_valid_accepts_active_and_locked() {
+    let env = Env::default();
+    env.ledger().set_timestamp(1000);
+    let (client, _admin) = setup(&env);
+    let user = Address::generate(&env);
+    client.register_document(&user, &hash(&env, 'a'), &name(&env, "A"));
+    client.register_document_with_options(&user, &hash(&env, 'b'), &name(&env, "B"), &locked_until(2000));
+
+    assert!(client.is_document_valid(&hash(&env, 'a')));
+    assert!(client.is_document_valid(&hash(&env, 'b')));
+}
+
+#[test]
+fn is_document_valid_rejects_unknown_hash() {
+    let env = Env::default();
+    let (client, _admin) = setup(&env);
+
+    assert!(!client.is_document_valid(&hash(&env, 'a')));
+}
+
+#[test]
+fn is_document_valid_rejects_deleted_document() {
+    let env = Env::default();
+    let (client, _admin) = setup(&env);
+    let user = Address::generate(&env);
+    client.register_document(&user, &hash(&env, 'a'), &name(&env, "A"));
+    client.delete_document(&user, &hash(&env, 'a'));
+
+    assert!(!client.is_document_valid(&hash(&env, 'a')));
+}
+
+#[test]
+fn deleted_hash_stays_tombstoned_until_cleared() {
+    let env = Env::default();
+    let (client, admin) = setup(&env);
+    let user = Address::generate(&env);
+    client.register_document(&user, &hash(&env, 'a'), &name(&env, "A"));
+    client.delete_document(&user, &hash(&env, 'a'));
+
+    assert_eq!(
+        client.try_register_document(&user, &hash(&env, 'a'), &name(&env, "A")),
+        Err(Ok(ContractError::HashTombstoned))
+    );
+    client.clear_tombstone(&admin, &hash(&env, 'a'));
+    assert_eq!(client.verify_document(&hash(&env, 'a')).status, DocumentStatus::NotFound);
+    client.register_document(&user, &hash(&env, 'a'), &name(&env, "A"));
+}
+
+#[test]
+fn lookups_accept_prefixed_hashes() {
+    let env = Env::default();
+    env.ledger().set_timestamp(1000);
+    let (client, _admin) = setup(&env);
+    let user = Address::generate(&env);
+    let bare = "cd".repeat(32);
+    let prefixed = name(&env, &std::format!("0x{bare}"));
+    client.register_document(&user, &name(&env, &bare), &name(&env, "A"));
+
+    let hashes = Vec::from_array(&env, [prefixed.clone(), hash(&env, 'e')]);
+    assert_eq!(client.find_missing_hashes(&hashes), Vec::from_array(&env, [hash(&env, 'e')]));
+    assert!(client.all_owned_by(&Vec::from_array(&env, [prefixed.clone()]), &user));
+    assert!(client.was_registered_before(&prefixed, &1000));
+    assert!(client.existed_before(&prefixed, &1000));
+    assert_eq!(client.get_document_age(&prefixed), Some(0));
+}
+
+#[test]
+fn verify_proof_consumes_challenge() {
+    let env = Env::default();
+    env.ledger().set_timestamp(1000);
+    let (client, _admin) = setup(&env);
+    let user = Address::generate(&env);
+    client.register_document(&user, &hash(&env, 'a'), &name(&env, "A"));
+    let challenge = client.request_proof(&user, &hash(&env, 'a'));
+
+    assert_eq!(
+        client.try_verify_proof(&user, &hash(&env, 'a'), &(challenge.nonce + 1)),
+        Err(Ok(ContractError::ProofChallengeInvalid))
+    );
+    client.verify_proof(&user, &hash(&env, 'a'), &challenge.nonce);
+    assert!(client.get_proof_challenge(&user, &hash(&env, 'a')).is_none());
+    assert_eq!(
+        client.try_verify_proof(&user, &hash(&env, 'a'), &challenge.nonce),
+        Err(Ok(ContractError::ProofChallengeInvalid))
+    );
+
+    let challenge = client.request_proof(&user, &hash(&env, 'a'));
+    env.ledger().set_timestamp(challenge.expires_at + 1);
+    assert_eq!(
+        client.try_verify_proof(&user, &hash(&env, 'a'), &challenge.nonce),
+        Err(Ok(ContractError::ProofChallengeExpired))
+    );
+}
+
+#[test]
+fn transfer_and_delete_drop_proof_challenges() {
+    let env = Env::default();
+    let (client, _admin) = setup(&env);
+    let user = Address::generate(&env);
+    let other = Address::generate(&env);
+    client.register_document(&user, &hash(&env, 'a'), &name(&env, "A"));
+    client.register_document(&user, &hash(&env, 'b'), &name(&env, "B"));
+    client.request_proof(&user, &hash(&env, 'a'));
+    let challenge = client.request_proof(&user, &hash(&env, 'b'));
+
+    let hashes = Vec::from_array(&env, [hash(&env, 'b')]);
+    client.transfer_documents_batch(&user, &hashes, &other);
+    assert!(client.get_proof_challenge(&user, &hash(&env, 'b')).is_none());
+    client.transfer_documents_batch(&other, &hashes, &user);
+    assert_eq!(
+        client.try_verify_proof(&user, &hash(&env, 'b'), &challenge.nonce),
+        Err(Ok(ContractError::ProofChallengeInvalid))
+    );
+
+    client.delete_document(&user, &hash(&env, 'a'));
+    assert!(client.get_proof_challenge(&user, &hash(&env, 'a')).is_none());
+}
+
+#[test]
+fn document_seq_is_not_reused_after_deletion() {
+    let env = Env::default();
+    let (client, _admin) = setup(&env);
+    let user = Address::generate(&env);
+    client.register_document(&user, &hash(&env, 'a'), &name(&env, "A"));
+    let second = client.register_document(&user, &hash(&env, 'b'), &name(&env, "B"));
+    client.delete_document(&user, &hash(&env, 'a'));
+    let third = client.register_document(&user, &hash(&env, 'c'), &name(&env, "C"));
+
+    assert_eq!((second, third), (2, 3));
+    assert_eq!(client.get_document_seq(&hash(&env, 'b')), Some(second));
+    assert_eq!(client.get_document_seq(&hash(&env, 'c')), Some(third));
+    assert_eq!(client.get_document_seq(&hash(&env, 'a')), None);
+}
+
+#[test]
+fn rekey_tombstones_old_hash() {
+    let env = Env::default();
+    let (client, _admin) = setup(&env);
+    let user = Address::generate(&env);
+    let other = Address::generate(&env);
+    client.register_document(&user, &hash(&env, 'a'), &name(&env, "A"));
+    client.rekey_document(&user, &hash(&env, 'a'), &hash(&env, 'b'));
+
+    assert_eq!(client.verify_document(&hash(&env, 'a')).status, DocumentStatus::Deleted);
+    assert_eq!(
+        client.try_register_document(&other, &hash(&env, 'a'), &name(&env, "A")),
+        Err(Ok(ContractError::HashTombstoned))
+    );
+    let history = client.get_document_history(&hash(&env, 'a'));
+    assert_eq!(history.len(), 1);
+    assert_eq!(history.first().unwrap().action, HistoryAction::Deleted);
+}
+
+#[test]
+fn rekey_rejects_sealed_document() {
+    let env = Env::default();
+    let (client, _admin) = setup(&env);
+    let user = Address::generate(&env);
+    client.register_document(&user, &hash(&env, 'a'), &name(&env, "A"));
+    let issuer = SigningKey::from_bytes(&[9; 32]);
+    let issuer_key = BytesN::from_array(&env, &issuer.verifying_key().to_bytes());
+    let seal = BytesN::from_array(&env, &issuer.sign(&[b'a'; 64]).to_bytes());
+    client.attach_seal(&user, &hash(&env, 'a'), &issuer_key, &seal);
+
+    assert_eq!(
+        client.try_rekey_document(&user, &hash(&env, 'a'), &hash(&env, 'b')),
+        Err(Ok(ContractError::DocumentSealed))
+    );
+}
+
+#[test]
+fn private_lists_hide_global_listings() {
+    let env = Env::default();
+    let (client, admin) = setup(&env);
+    let user = Address::generate(&env);
+    client.register_document(&user, &hash(&env, 'a'), &name(&env, "A"));
+    client.add_tag(&user, &hash(&env, 'a'), &name(&env, "tax"));
+    client.set_private_user_lists(&admin, &true);
+
+    assert_eq!(
+        client.try_get_documents_by_tag(&None, &name(&env, "tax"), &0, &10),
+        Err(Ok(ContractError::Unauthorized))
+    );
+    assert_eq!(
+        client.try_get_all_registrants(&Some(user.clone()), &0, &10),
+        Err(Ok(ContractError::Unauthorized))
+    );
+    assert_eq!(client.get_documents_by_tag(&Some(admin.clone()), &name(&env, "tax"), &0, &10).len(), 1);
+    assert_eq!(client.get_all_registrants(&Some(admin), &0, &10).len(), 1);
+    assert_eq!(client.get_user_documents(&user).len(), 1);
+    assert!(client.is_document_name_used(&user, &name(&env, "A")));
+}
+
+#[test]
+fn get_document_by_name_returns_first_match() {
+    let env = Env::default();
+    let (client, _admin) = setup(&env);
+    let user = Address::generate(&env);
+    let other = Address::generate(&env);
+    client.register_document(&user, &hash(&env, 'a'), &name(&env, "A"));
+    client.register_document(&user, &hash(&env, 'b'), &name(&env, "B"));
+
+    let info = client.get_document_by_name(&user, &name(&env, "B"));
+    assert!(info.exists);
+    assert_eq!(info.record.first().unwrap().document_hash, hash(&env, 'b'));
+    assert!(!client.get_document_by_name(&user, &name(&env, "C")).exists);
+    assert!(!client.get_document_by_name(&other, &name(&env, "A")).exists);
+}
+
+#[test]
+fn temporary_registration_checks_names() {
+    let env = Env::default();
+    let (client, admin) = setup(&env);
+    let user = Address::generate(&env);
+    let other = Address::generate(&env);
+    client.register_document(&user, &hash(&env, 'a'), &name(&env, "Report 1"));
+    client.set_global_name_unique(&admin, &true);
+    assert_eq!(
+        client.try_register_document_temporary(&other, &hash(&env, 'b'), &name(&env, "Report 1"), &100),
+        Err(Ok(ContractError::DocumentNameTaken))
+    );
+
+    client.set_global_name_unique(&admin, &false);
+    client.set_strict_names(&admin, &true);
+    assert_eq!(
+        client.try_register_document_temporary(&user, &hash(&env, 'b'), &name(&env, " Report  1"), &100),
+        Err(Ok(ContractError::DocumentNameTaken))
+    );
+
+    client.register_document_temporary(&user, &hash(&env, 'b'), &name(&env, "Report 2"), &100);
+    assert!(client.verify_document(&hash(&env, 'b')).exists);
+    client.register_document_requiring(&user, &hash(&env, 'c'), &name(&env, "Report 3"), &hash(&env, 'b'));
+}
+
+#[test]
+fn delete_accepts_prefixed_hash() {
+    let env = Env::default();
+    let (client, _admin) = setup(&env);
+    let user = Address::generate(&env);
+    let bare = "ef".repeat(32);
+    client.register_document(&user, &name(&env, &bare), &name(&env, "A"));
+    client.register_document(&user, &hash(&env, 'b'), &name(&env, "B"));
+
+    client.delete_document(&user, &name(&env, &std::format!("0x{bare}")));
+    assert_eq!(client.verify_document(&name(&env, &bare)).status, DocumentStatus::Deleted);
+    let prefixed = name(&env, &std::format!("0X{}", "b".repeat(64)));
+    assert_eq!(client.delete_documents_batch(&user, &Vec::from_array(&env, [prefixed])), 1);
+    assert_eq!(client.get_document_count(), 0);
+}
+
+#[test]
+fn cursor_survives_deleting_its_document() {
+    let env = Env::default();
+    let (client, _admin) = setup(&env);
+    let user = Address::generate(&env);
+    for c in ['a', 'b', 'c', 'd', 'e'] {
+        client.register_document(&user, &hash(&env, c), &name(&env, &std::format!("{c}")));
+    }
+
+    let (page, cursor) = client.get_user_documents_cursor(&None, &user, &None, &2);
+    assert_eq!(page.len(), 2);
+    assert_eq!(cursor, Some(2));
+    client.delete_document(&user, &hash(&env, 'b'));
+    let (page, cursor) = client.get_user_documents_cursor(&None, &user, &cursor, &2);
+    assert_eq!(page.first().unwrap().document_hash, hash(&env, 'c'));
+    assert_eq!(cursor, Some(4));
+    let (page, cursor) = client.get_user_documents_cursor(&None, &user, &cursor, &0);
+    assert_eq!(page.len(), 1);
+    assert_eq!(cursor, None);
+}
+
+#[test]
+fn transfer_keeps_receiver_list_in_seq_order() {
+    let env = Env::default();
+    let (client, _admin) = setup(&env);
+    let user = Address::generate(&env);
+    let other = Address::generate(&env);
+    client.register_document(&other, &hash(&env, 'a'), &name(&env, "A"));
+    client.register_document(&user, &hash(&env, 'b'), &name(&env, "B"));
+    client.register_document(&other, &hash(&env, 'c'), &name(&env, "C"));
+    client.transfer_documents_batch(&other, &Vec::from_array(&env, [hash(&env, 'c'), hash(&env, 'a')]), &user);
+
+    let seqs: std::vec::Vec<u64> = client.get_user_documents(&user).iter().map(|record| record.seq).collect();
+    assert_eq!(seqs, [1, 2, 3]);
+}
+
+#[test]
+fn requiring_rejects_locked_and_deleted_prerequisites() {
+    let env = Env::default();
+    env.ledger().set_timestamp(1000);
+    let (client, _admin) = setup(&env);
+    let user = Address::generate(&env);
+    client.register_document_with_options(&user, &hash(&env, 'a'), &name(&env, "A"), &locked_until(2000));
+    client.register_document(&user, &hash(&env, 'b'), &name(&env, "B"));
+    client.delete_document(&user, &hash(&env, 'b'));
+
+    for required in ['a', 'b', 'e'] {
+        assert_eq!(
+            client.try_register_document_requiring(&user, &hash(&env, 'c'), &name(&env, "C"), &hash(&env, required)),
+            Err(Ok(ContractError::RequiredDocumentMissing))
+        );
+    }
+    env.ledger().set_timestamp(2000);
+    client.register_document_requiring(&user, &hash(&env, 'c'), &name(&env, "C"), &hash(&env, 'a'));
+}
+
+#[test]
+fn relations_are_capped_and_follow_their_target() {
+    let env = Env::default();
+    let (client, _admin) = setup(&env);
+    let user = Address::generate(&env);
+    let relation = soroban_sdk::symbol_short!("cites");
+    client.register_document(&user, &hash(&env, 'a'), &name(&env, "A"));
+    client.register_document(&user, &hash(&env, 'b'), &name(&env, "B"));
+    client.register_document(&user, &hash(&env, 'c'), &name(&env, "C"));
+
+    assert_eq!(
+        client.try_link_documents(&user, &hash(&env, 'a'), &hash(&env, 'a'), &relation),
+        Err(Ok(ContractError::InvalidRelation))
+    );
+    client.link_documents(&user, &hash(&env, 'a'), &hash(&env, 'b'), &relation);
+    client.link_documents(&user, &hash(&env, 'a'), &hash(&env, 'c'), &relation);
+    client.rekey_document(&user, &hash(&env, 'b'), &hash(&env, 'd'));
+    assert_eq!(
+        client.get_related_documents(&hash(&env, 'a')),
+        Vec::from_array(&env, [(relation.clone(), hash(&env, 'd')), (relation.clone(), hash(&env, 'c'))])
+    );
+    client.delete_document(&user, &hash(&env, 'c'));
+    assert_eq!(
+        client.get_related_documents(&hash(&env, 'a')),
+        Vec::from_array(&env, [(relation.clone(), hash(&env, 'd'))])
+    );
+
+    for n in 0..(MAX_LINKS - 1) {
+        let target = name(&env, &std::format!("{:064x}", n + 1));
+        client.register_document(&user, &target, &name(&env, &std::format!("T{n}")));
+        client.link_documents(&user, &hash(&env, 'a'), &target, &relation);
+    }
+    client.register_document(&user, &hash(&env, 'e'), &name(&env, "E"));
+    assert_eq!(
+        client.try_link_documents(&user, &hash(&env, 'a'), &hash(&env, 'e'), &relation),
+        Err(Ok(ContractError::TooManyLinks))
+    );
+}
+
+#[test]
+fn merge_respects_max_batch_size() {
+    let env = Env::default();
+    let (client, admin) = setup(&env);
+    let from = Address::generate(&env);
+    let to = Address::generate(&env);
+    client.register_document(&from, &hash(&env, 'a'), &name(&env, "A"));
+    client.set_max_batch_size(&admin, &1);
+
+    assert_eq!(
+        client.try_merge_user_documents(&from, &from, &to, &2),
+        Err(Ok(ContractError::BatchTooLarge))
+    );
+    assert_eq!(client.merge_user_documents(&from, &from, &to, &1), 1);
+}
+
+#[test]
+fn initialize_with_config_checks_every_limit() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(DocumentVerificationContract, ());
+    let client = DocumentVerificationContractClient::new(&env, &contract_id);
+    let admin = Address::generate(&env);
+    let valid = ContractConfig {
+        strict_names: false,
+        global_name_unique: false,
+        event_mode: EventMode::Full,
+        max_batch_size: MAX_BATCH_SIZE_LIMIT,
+        private_user_lists: false,
+        min_registration_interval: MAX_REGISTRATION_INTERVAL,
+        registration_cooldown: MAX_REGISTRATION_COOLDOWN,
+    };
+
+    let mut config = valid.clone();
+    config.max_batch_size = MAX_BATCH_SIZE_LIMIT + 1;
+    assert_eq!(client.try_initialize_with_config(&admin, &config), Err(Ok(ContractError::InvalidConfig)));
+    let mut config = valid.clone();
+    config.min_registration_interval = MAX_REGISTRATION_INTERVAL + 1;
+    assert_eq!(client.try_initialize_with_config(&admin, &config), Err(Ok(ContractError::InvalidConfig)));
+    let mut config = valid.clone();
+    config.registration_cooldown = MAX_REGISTRATION_COOLDOWN + 1;
+    assert_eq!(client.try_initialize_with_config(&admin, &config), Err(Ok(ContractError::InvalidConfig)));
+
+    client.initialize_with_config(&admin, &valid);
+    assert_eq!(client.get_registration_cooldown(), MAX_REGISTRATION_COOLDOWN);
+    assert_eq!(
+        client.try_set_registration_cooldown(&admin, &(MAX_REGISTRATION_COOLDOWN + 1)),
+        Err(Ok(ContractError::InvalidConfig))
+    );
+    assert_eq!(
+        client.try_set_min_registration_interval(&admin, &(MAX_REGISTRATION_INTERVAL + 1)),
+        Err(Ok(ContractError::InvalidConfig))
+    );
+    assert_eq!(
+        client.try_set_max_batch_size(&admin, &(MAX_BATCH_SIZE_LIMIT + 1)),
+        Err(Ok(ContractError::InvalidConfig))
+    );
+}
@@ -22,7 +22,8 @@ export interface DocumentRecord {
 
 export interface DocumentInfo {
     exists: boolean
-    record?: DocumentRecord
+    // Holds the record as a single entry when the document exists
+    record: DocumentRecord[]
 }
 
 export interface VerificationResult {
@@ -238,16 +239,17 @@ export class SorobanDocumentVerification {
 
             const documentInfo = StellarSdk.scValToNative(result) as DocumentInfo
 
-            if (!documentInfo.exists || !documentInfo.record) {
+            const record = documentInfo.record?.[0]
+            if (!documentInfo.exists || !record) {
                 return { isVerified: false }
             }
 
             return {
                 isVerified: true,
-                registeredBy: documentInfo.record.registered_by,
-                registeredAt: new Date(Number.parseInt(documentInfo.record.timestamp) * 1000).toISOString(),
-                documentName: documentInfo.record.document_name,
-                blockNumber: documentInfo.record.block_number,
+                registeredBy: record.registered_by,
+                registeredAt: new Date(Number.parseInt(record.timestamp) * 1000).toISOString(),
+                documentName: record.document_name,
+                blockNumber: record.block_number,
             }
         } catch (error: any) {
             throw new ContractError(`Verification failed: ${error.message}`)